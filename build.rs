@@ -0,0 +1,4 @@
+fn main() {
+    // Expose `Py_LIMITED_API`, `Py_3_10` etc. to decide which strings can be borrowed
+    pyo3_build_config::use_pyo3_cfgs();
+}
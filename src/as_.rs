@@ -0,0 +1,145 @@
+//! Modules for use with `#[serde(with = "...")]`, to choose the Python type of a field where
+//! serde's data model has no equivalent: [`tuple`](mod@tuple), [`set`], [`frozenset`],
+//! [`bytes`] and [`decimal`].
+//!
+//! ```
+//! use std::collections::BTreeSet;
+//!
+//! use pyo3::prelude::*;
+//! use pythonize::{depythonize, pythonize};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Tags {
+//!     #[serde(with = "pythonize::as_::set")]
+//!     names: BTreeSet<String>,
+//! }
+//!
+//! Python::attach(|py| {
+//!     let tags = Tags { names: BTreeSet::from(["a".to_string()]) };
+//!     let obj = pythonize(py, &tags).unwrap();
+//!     assert_eq!(obj.repr().unwrap().to_string(), "{'names': {'a'}}");
+//!
+//!     let tags: Tags = depythonize(&obj).unwrap();
+//!     assert!(tags.names.contains("a"));
+//! })
+//! ```
+//!
+//! Other serializers and deserializers treat these fields as if the attribute were not there.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+pub(crate) const SET_TOKEN: &str = "$pythonize::private::Set";
+pub(crate) const FROZENSET_TOKEN: &str = "$pythonize::private::FrozenSet";
+pub(crate) const TUPLE_TOKEN: &str = "$pythonize::private::Tuple";
+pub(crate) const BYTES_TOKEN: &str = "$pythonize::private::Bytes";
+pub(crate) const DECIMAL_TOKEN: &str = "$pythonize::private::Decimal";
+
+/// Serialize `value` as a newtype struct named `token`, which `Pythonizer` recognises
+fn serialize<T, S>(token: &'static str, value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ?Sized + Serialize,
+    S: Serializer,
+{
+    serializer.serialize_newtype_struct(token, value)
+}
+
+fn deserialize<'de, T, D>(token: &'static str, deserializer: D) -> Result<T, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_newtype_struct(token, TokenVisitor(PhantomData))
+}
+
+struct TokenVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for TokenVisitor<T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a newtype struct")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
+
+macro_rules! with_module {
+    ($(#[$attr:meta])* $name:ident => $token:ident) => {
+        $(#[$attr])*
+        pub mod $name {
+            use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+            /// Serialize with `#[serde(serialize_with = "...")]`
+            pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                T: ?Sized + Serialize,
+                S: Serializer,
+            {
+                super::serialize(super::$token, value, serializer)
+            }
+
+            /// Deserialize with `#[serde(deserialize_with = "...")]`
+            pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+            where
+                T: Deserialize<'de>,
+                D: Deserializer<'de>,
+            {
+                super::deserialize(super::$token, deserializer)
+            }
+        }
+    };
+}
+
+with_module! {
+    /// Serialize a sequence (e.g. a `Vec`) as a Python `tuple`, or the sequence created by
+    /// [`PythonizeTypes::create_tuple`](crate::PythonizeTypes::create_tuple).
+    ///
+    /// Deserialization accepts any sequence, as for the type itself.
+    tuple => TUPLE_TOKEN
+}
+
+with_module! {
+    /// Serialize a sequence (e.g. a `HashSet` or `BTreeSet`) as a Python `set`.
+    ///
+    /// Deserialization accepts any sequence, as for the type itself.
+    set => SET_TOKEN
+}
+
+with_module! {
+    /// Serialize a sequence (e.g. a `HashSet` or `BTreeSet`) as a Python `frozenset`.
+    ///
+    /// Deserialization accepts any sequence, as for the type itself.
+    frozenset => FROZENSET_TOKEN
+}
+
+with_module! {
+    /// Serialize a sequence of `u8` (e.g. a `Vec<u8>` or `[u8; N]`) as Python `bytes`, or the
+    /// object created by [`PythonizeTypes::create_bytes`](crate::PythonizeTypes::create_bytes).
+    ///
+    /// Deserialization accepts `bytes` as well as any sequence of integers.
+    bytes => BYTES_TOKEN
+}
+
+with_module! {
+    /// Serialize a number or numeric string as a Python `decimal.Decimal`. Floats are converted
+    /// through their shortest representation, so `0.1` becomes `Decimal('0.1')`.
+    ///
+    /// When deserializing, a `Decimal` is presented as its string representation (or as a float or
+    /// integer, if one is requested), so that types such as `rust_decimal::Decimal` can be read
+    /// without loss of precision. Integers are only read from integral decimals. Other values are
+    /// deserialized as usual.
+    decimal => DECIMAL_TOKEN
+}
@@ -0,0 +1,122 @@
+use std::borrow::Cow;
+
+/// The case of struct field names in Python mappings, chosen with
+/// [`Pythonizer::with_field_case`](crate::Pythonizer::with_field_case) and
+/// [`Depythonizer::with_field_case`](crate::Depythonizer::with_field_case).
+///
+/// Rust field names are expected to be in `snake_case`, and are converted in the same way as by
+/// `#[serde(rename_all = "...")]`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FieldCase {
+    /// `field_name`, the names of the Rust fields as they are
+    #[default]
+    SnakeCase,
+    /// `fieldName`
+    CamelCase,
+    /// `FieldName`
+    PascalCase,
+    /// `field-name`
+    KebabCase,
+}
+
+impl FieldCase {
+    /// Convert the `snake_case` name of a Rust field to this case
+    pub(crate) fn apply(self, field: &str) -> Cow<'_, str> {
+        match self {
+            FieldCase::SnakeCase => Cow::Borrowed(field),
+            _ => Cow::Owned(self.chars(field).collect()),
+        }
+    }
+
+    /// The characters of the `snake_case` name of a Rust field converted to this case, so that
+    /// names can be compared without allocating
+    fn chars(self, field: &str) -> impl Iterator<Item = char> + '_ {
+        let mut capitalize = self == FieldCase::PascalCase;
+        let mut first = true;
+        field.chars().filter_map(move |ch| {
+            let ch = match self {
+                FieldCase::SnakeCase => ch,
+                FieldCase::KebabCase if ch == '_' => '-',
+                FieldCase::KebabCase => ch,
+                FieldCase::CamelCase | FieldCase::PascalCase if ch == '_' => {
+                    capitalize = true;
+                    return None;
+                }
+                FieldCase::CamelCase if first => {
+                    capitalize = false;
+                    ch.to_ascii_lowercase()
+                }
+                FieldCase::CamelCase | FieldCase::PascalCase if capitalize => {
+                    capitalize = false;
+                    ch.to_ascii_uppercase()
+                }
+                FieldCase::CamelCase | FieldCase::PascalCase => ch,
+            };
+            first = false;
+            Some(ch)
+        })
+    }
+
+    /// The field among `fields` whose name is `key` in this case, if any
+    pub(crate) fn find_field(
+        self,
+        fields: &'static [&'static str],
+        key: &str,
+    ) -> Option<&'static str> {
+        fields
+            .iter()
+            .copied()
+            .find(|field| self.chars(field).eq(key.chars()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FieldCase;
+
+    #[test]
+    fn test_apply() {
+        let fields = ["name", "max_retry_count", "x_1"];
+        let converted = |case: FieldCase| {
+            fields
+                .iter()
+                .map(|field| case.apply(field).into_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(converted(FieldCase::SnakeCase), fields);
+        assert_eq!(
+            converted(FieldCase::CamelCase),
+            ["name", "maxRetryCount", "x1"]
+        );
+        assert_eq!(
+            converted(FieldCase::PascalCase),
+            ["Name", "MaxRetryCount", "X1"]
+        );
+        assert_eq!(
+            converted(FieldCase::KebabCase),
+            ["name", "max-retry-count", "x-1"]
+        );
+    }
+
+    #[test]
+    fn test_find_field() {
+        const FIELDS: &[&str] = &["name", "max_retry_count"];
+        assert_eq!(
+            FieldCase::CamelCase.find_field(FIELDS, "maxRetryCount"),
+            Some("max_retry_count")
+        );
+        assert_eq!(
+            FieldCase::KebabCase.find_field(FIELDS, "max-retry-count"),
+            Some("max_retry_count")
+        );
+        assert_eq!(
+            FieldCase::PascalCase.find_field(FIELDS, "Name"),
+            Some("name")
+        );
+        assert_eq!(FieldCase::CamelCase.find_field(FIELDS, "maxRetry"), None);
+        assert_eq!(
+            FieldCase::CamelCase.find_field(FIELDS, "max_retry_count"),
+            None
+        );
+    }
+}
@@ -1,9 +1,19 @@
-use pyo3::exceptions::PyKeyError;
-use pyo3::{intern, types::*, Bound};
-use serde::de::{self, IntoDeserializer};
-use serde::Deserialize;
-
+use std::borrow::Cow;
+use std::marker::PhantomData;
+
+use pyo3::exceptions::PyTypeError;
+use pyo3::sync::PyOnceLock;
+use pyo3::{ffi, intern, types::*, Borrowed, Bound, Py, PyResult, Python};
+use serde::de::value::SeqDeserializer;
+use serde::de::{self, DeserializeOwned, IntoDeserializer};
+use serde::{Deserialize, Serialize};
+
+use crate::as_;
+use crate::case::FieldCase;
 use crate::error::{ErrorImpl, PythonizeError, Result};
+use crate::object::{self, OBJECT_TOKEN};
+use crate::ser::{Marked, Pythonizer};
+use crate::value::VALUE_TOKEN;
 
 #[cfg(feature = "arbitrary_precision")]
 const TOKEN: &str = "$serde_json::private::Number";
@@ -14,10 +24,23 @@ const TOKEN: &str = "$serde_json::private::Number";
 /// - integers (including arbitrary precision integers if the `arbitrary_precision` feature is enabled)
 /// - floats
 /// - strings
-/// - bytes
+/// - bytes (including `bytearray`, `memoryview` and other contiguous buffers of bytes)
 /// - `collections.abc.Sequence` instances (as serde sequences)
 /// - `collections.abc.Mapping` instances (as serde maps)
 /// - dataclasses (as serde maps)
+///
+/// Mapping keys are deserialized like any other value, so `int`, `float`, `bool`, `tuple` and
+/// `frozenset` keys can be read as the equivalent Rust types (e.g. `HashMap<(i32, i32), T>`).
+/// Members of `enum.Enum` are read as their name wherever a string or a Rust enum is expected.
+/// Use [`Depythonizer::with_stringified_keys`] to read keys which are not `str` as strings.
+/// Other iterables, such as generators, can be read as sequences with
+/// [`Depythonizer::with_iterables`].
+///
+/// Strings and bytes can be borrowed (e.g. as `&'a str` or `Cow<'a, str>`) from `obj` itself
+/// and from the items of tuples within it, which are guaranteed to live as long as `obj`.
+/// Items of mutable containers such as `list` and `dict` could be released while the result is
+/// still in use, so they are always copied. Strings are also copied when using the limited
+/// Python API before Python 3.10, which cannot expose their UTF-8 contents.
 pub fn depythonize<'a, 'py, T>(obj: &'a Bound<'py, PyAny>) -> Result<T>
 where
     T: Deserialize<'a>,
@@ -25,35 +48,277 @@ where
     T::deserialize(&mut Depythonizer::from_object(obj))
 }
 
+/// Iterate over the items of a Python sequence, converting each of them to an instance of `T`
+/// only when it is reached, e.g. to process a long list without collecting it into a `Vec<T>`.
+///
+/// ```
+/// use pyo3::prelude::*;
+/// use pythonize::depythonize_iter;
+///
+/// Python::attach(|py| {
+///     let obj = py.eval(c"[1, 2, 'three']", None, None).unwrap();
+///     let mut items = depythonize_iter::<u32>(&obj).unwrap();
+///     assert_eq!(items.next().unwrap().unwrap(), 1);
+///     assert_eq!(items.next().unwrap().unwrap(), 2);
+///     let err = items.next().unwrap().unwrap_err();
+///     assert!(err.to_string().starts_with("item 2: "));
+/// })
+/// ```
+///
+/// The same objects as for a `Vec<T>` are accepted; see [`Depythonizer::iter`].
+pub fn depythonize_iter<'a, 'py, T>(
+    obj: &'a Bound<'py, PyAny>,
+) -> Result<DepythonizeIter<'a, 'py, T>>
+where
+    T: Deserialize<'a>,
+{
+    Depythonizer::from_object(obj).iter()
+}
+
+/// Update `value` from a Python object, e.g. to reload a configuration: only the fields and map
+/// entries present in `obj` are replaced, and the rest of `value` is kept.
+///
+/// ```
+/// use pyo3::prelude::*;
+/// use pythonize::depythonize_into;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Config {
+///     name: String,
+///     limits: Limits,
+/// }
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Limits {
+///     memory: u32,
+///     cpus: u32,
+/// }
+///
+/// Python::attach(|py| {
+///     let mut config = Config { name: "x".into(), limits: Limits { memory: 512, cpus: 1 } };
+///     let patch = py.eval(c"{'limits': {'cpus': 4}}", None, None).unwrap();
+///     depythonize_into(&patch, &mut config).unwrap();
+///     assert_eq!(config.name, "x");
+///     assert_eq!((config.limits.memory, config.limits.cpus), (512, 4));
+/// })
+/// ```
+///
+/// This is not done in place: `value` is first converted to Python with
+/// [`pythonize`](crate::pythonize), then mappings in `obj` are merged into it recursively, and any
+/// other object replaces the current one (so sequences are replaced as a whole). Enums are
+/// replaced as a whole too, so a patch may switch to another variant, but must give all the
+/// fields of a struct variant. The result is then read back with `T::deserialize_in_place`, which
+/// replaces `value` entirely unless serde_derive's `deserialize_in_place` feature is enabled.
+///
+/// As a consequence:
+/// - `T` must be read back from its own serialized form, even when `obj` is empty, so types
+///   whose `Serialize` and `Deserialize` implementations do not match cannot be updated.
+/// - Fields which are not serialized (`#[serde(skip)]`, `skip_serializing` and
+///   `skip_serializing_if`) are reset to their default on every update, unless `obj` gives them.
+/// - If reading the result fails, `value` is left unchanged (or may be partially updated with
+///   serde_derive's `deserialize_in_place` feature).
+pub fn depythonize_into<T>(obj: &Bound<'_, PyAny>, value: &mut T) -> Result<()>
+where
+    T: Serialize + DeserializeOwned,
+{
+    Depythonizer::from_object(obj).deserialize_into(value)
+}
+
 /// A structure that deserializes Python objects into Rust values
 pub struct Depythonizer<'a, 'py> {
-    input: &'a Bound<'py, PyAny>,
+    input: Input<'a, 'py>,
+    options: DepythonizerOptions,
+    /// Whether `input` is the key of a mapping
+    key: bool,
+}
+
+/// Settings chosen with the `with_*` methods of [`Depythonizer`], which apply to the whole object
+/// being deserialized
+#[derive(Clone, Copy, Default)]
+struct DepythonizerOptions {
+    stringify_keys: bool,
+    iterables: bool,
+    field_case: FieldCase,
+}
+
+impl DepythonizerOptions {
+    /// The name of the field among `fields` which a mapping `key` refers to in the chosen case,
+    /// or `key` itself
+    fn field_key<'py>(
+        &self,
+        key: Bound<'py, PyAny>,
+        fields: &'static [&'static str],
+    ) -> Result<Bound<'py, PyAny>> {
+        if self.field_case == FieldCase::SnakeCase {
+            return Ok(key);
+        }
+        if let Ok(name) = key.cast::<PyString>() {
+            if let Some(field) = self.field_case.find_field(fields, &name.to_cow()?) {
+                return Ok(PyString::new(key.py(), field).into_any());
+            }
+        }
+        Ok(key)
+    }
+}
+
+/// The object being deserialized
+enum Input<'a, 'py> {
+    /// An object which lives for at least `'a`, so that data can be borrowed from it
+    Borrowed(Borrowed<'a, 'py, PyAny>),
+    /// An object taken from a mutable container, which may be released before `'a` ends
+    Owned(Bound<'py, PyAny>),
 }
 
 impl<'a, 'py> Depythonizer<'a, 'py> {
     /// Create a deserializer from a Python object
     pub fn from_object(input: &'a Bound<'py, PyAny>) -> Self {
-        Depythonizer { input }
+        Self::from_borrowed(input.as_borrowed(), DepythonizerOptions::default())
+    }
+
+    /// Deserialize mapping keys which are not `str` (`int`, `float`, `bool` and `None`) as strings
+    /// when a string is requested, in the same format as `json.dumps`, and `enum.Enum` members as
+    /// their name. This allows reading any `dict` with JSON-compatible keys as a
+    /// `serde_json::Value`.
+    pub fn with_stringified_keys(mut self, enabled: bool) -> Self {
+        self.options.stringify_keys = enabled;
+        self
+    }
+
+    /// Deserialize sequences from any iterable object, such as generators, `map` objects and file
+    /// objects, which are consumed one item at a time. Fixed-length tuples read at most one item
+    /// more than their length, so that a longer (or endless) iterable is rejected without
+    /// consuming the rest of it. `str`, `bytes` and mappings are never treated as iterables.
+    pub fn with_iterables(mut self, enabled: bool) -> Self {
+        self.options.iterables = enabled;
+        self
+    }
+
+    /// Update `value` with the contents of the input, with the options of this deserializer. See
+    /// [`depythonize_into`].
+    pub fn deserialize_into<T>(self, value: &mut T) -> Result<()>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let current = value.serialize(Pythonizer::for_merge(
+            self.input().py(),
+            self.options.field_case,
+        ))?;
+        let merged = merge_into(current, self.input(), self.options.field_case)?;
+        T::deserialize_in_place(
+            &mut Depythonizer::from_borrowed(merged.as_borrowed(), self.options),
+            value,
+        )
+    }
+
+    /// Read the fields of structs from mapping keys in the given case (e.g. `maxRetries` for a
+    /// `max_retries` field). Keys which match a field name as it is are still accepted. Only the
+    /// structs which serde reads with `deserialize_struct` are converted: structs with
+    /// `#[serde(flatten)]` fields, and the fields of internally tagged and untagged enums, are
+    /// read as map entries before serde knows their names, so they must be given as they are.
+    pub fn with_field_case(mut self, case: FieldCase) -> Self {
+        self.options.field_case = case;
+        self
+    }
+
+    /// Iterate over the items of the input, deserializing each of them to `T` as it is reached.
+    /// Sequences, sets and (with [`with_iterables`](Self::with_iterables)) iterables are
+    /// accepted. Errors are reported along with the index of the item which caused them.
+    pub fn iter<T>(self) -> Result<DepythonizeIter<'a, 'py, T>>
+    where
+        T: Deserialize<'a>,
+    {
+        let items = match self.sequence_access(None) {
+            Ok(seq) => Items::Sequence(seq),
+            Err(e) => {
+                if !matches!(*e.inner, ErrorImpl::UnexpectedType(_)) {
+                    return Err(e);
+                }
+                match self.set_access() {
+                    Ok(set) => Items::Iterator(set),
+                    Err(_) => match self.iterable_access()? {
+                        Some(iter) => Items::Iterator(iter),
+                        None => return Err(e),
+                    },
+                }
+            }
+        };
+        Ok(DepythonizeIter {
+            items,
+            index: 0,
+            _type: PhantomData,
+        })
+    }
+
+    fn from_borrowed(input: Borrowed<'a, 'py, PyAny>, options: DepythonizerOptions) -> Self {
+        Depythonizer {
+            input: Input::Borrowed(input),
+            options,
+            key: false,
+        }
+    }
+
+    fn from_owned(input: Bound<'py, PyAny>, options: DepythonizerOptions) -> Self {
+        Depythonizer {
+            input: Input::Owned(input),
+            options,
+            key: false,
+        }
+    }
+
+    fn from_key(input: Bound<'py, PyAny>, options: DepythonizerOptions) -> Self {
+        Depythonizer {
+            key: true,
+            ..Self::from_owned(input, options)
+        }
+    }
+
+    fn input(&self) -> &Bound<'py, PyAny> {
+        match &self.input {
+            Input::Borrowed(obj) => obj,
+            Input::Owned(obj) => obj,
+        }
     }
 
     fn sequence_access(&self, expected_len: Option<usize>) -> Result<PySequenceAccess<'a, 'py>> {
-        let seq = self.input.cast::<PySequence>()?;
-        let len = self.input.len()?;
+        let seq = match self.input().cast::<PySequence>() {
+            Ok(seq) => seq.clone(),
+            Err(e) => match (expected_len, self.iterable_access()?) {
+                // Read one item past the expected length, which is enough to tell that there are
+                // too many without reading the whole iterator
+                (Some(expected), Some(iter)) => {
+                    let items = iter.iter.take(expected + 1).collect::<PyResult<Vec<_>>>()?;
+                    PyList::new(self.input().py(), items)?.into_sequence()
+                }
+                _ => return Err(e.into()),
+            },
+        };
+        let len = seq.len()?;
 
         match expected_len {
             Some(expected) if expected != len => {
                 Err(PythonizeError::incorrect_sequence_length(expected, len))
             }
-            _ => Ok(PySequenceAccess::new(seq, len)),
+            _ => {
+                // Only the storage of exact tuples is known to be immutable
+                let items = match self.input {
+                    Input::Borrowed(obj) => match obj.cast_exact::<PyTuple>() {
+                        Ok(tuple) => PySequenceItems::Tuple(tuple),
+                        Err(_) => PySequenceItems::Sequence(seq),
+                    },
+                    Input::Owned(_) => PySequenceItems::Sequence(seq),
+                };
+                Ok(PySequenceAccess::new(items, len, self.options))
+            }
         }
     }
 
-    fn set_access(&self) -> Result<PySetAsSequence<'py>> {
-        match self.input.cast::<PySet>() {
-            Ok(set) => Ok(PySetAsSequence::from_set(set)),
+    fn set_access(&self) -> Result<PyIterableAccess<'py>> {
+        match self.input().cast::<PySet>() {
+            Ok(set) => Ok(PyIterableAccess::from_set(set, self.options)),
             Err(e) => {
-                if let Ok(f) = self.input.cast::<PyFrozenSet>() {
-                    Ok(PySetAsSequence::from_frozenset(f))
+                if let Ok(f) = self.input().cast::<PyFrozenSet>() {
+                    Ok(PyIterableAccess::from_frozenset(f, self.options))
                 } else {
                     Err(e.into())
                 }
@@ -61,18 +326,112 @@ impl<'a, 'py> Depythonizer<'a, 'py> {
         }
     }
 
-    fn dict_access(&self) -> Result<PyMappingAccess<'py>> {
-        PyMappingAccess::new(self.input.cast()?)
+    /// Iterate over `input` if iterables are enabled (see [`with_iterables`](Self::with_iterables))
+    fn iterable_access(&self) -> Result<Option<PyIterableAccess<'py>>> {
+        let obj = self.input();
+        if !self.options.iterables
+            || obj.is_instance_of::<PyString>()
+            || obj.is_instance_of::<PyBytes>()
+            || obj.is_instance_of::<PyByteArray>()
+            || obj.cast::<PyMapping>().is_ok()
+        {
+            return Ok(None);
+        }
+        match PyIterator::from_object(obj) {
+            Ok(iter) => Ok(Some(PyIterableAccess::from_iterator(iter, self.options))),
+            Err(err) if err.is_instance_of::<PyTypeError>(obj.py()) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn dict_access(&self) -> Result<PyMappingAccess<'a, 'py>> {
+        PyMappingAccess::new(self.input().cast()?, self.options)
+    }
+
+    fn namedtuple_access(&self) -> Result<Option<PyMappingAccess<'a, 'py>>> {
+        let Ok(tuple) = self.input().cast::<PyTuple>() else {
+            return Ok(None);
+        };
+        let fields = match tuple.getattr_opt(intern!(tuple.py(), "_fields"))? {
+            Some(fields) => match fields.cast_into::<PyTuple>() {
+                Ok(fields) => fields,
+                Err(_) => return Ok(None),
+            },
+            None => return Ok(None),
+        };
+        let values = match self.input {
+            Input::Borrowed(obj) => PySequenceItems::Tuple(obj.cast::<PyTuple>()?),
+            Input::Owned(_) => PySequenceItems::Sequence(tuple.to_list().into_sequence()),
+        };
+        PyMappingAccess::from_namedtuple(&fields, values, tuple.len(), self.options).map(Some)
+    }
+
+    /// Visit a mapping or dataclass, whose keys are the names of `fields` if it is a struct
+    fn visit_mapping<V>(&self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'a>,
+    {
+        if let Some(dc_access) = self.dataclass_access()? {
+            visitor.visit_map(dc_access.with_fields(fields))
+        } else {
+            visitor.visit_map(self.dict_access()?.with_fields(fields))
+        }
     }
 
     fn dataclass_access(&self) -> Result<Option<PyDataclassAccess<'py>>> {
-        if let Some(dc) = DataclassCandidate::try_new(self.input) {
-            Some(PyDataclassAccess::new(dc)).transpose()
+        if let Some(dc) = DataclassCandidate::try_new(self.input()) {
+            Some(PyDataclassAccess::new(dc, self.options)).transpose()
         } else {
             Ok(None)
         }
     }
 
+    /// The name of `input` if it is an `enum.Enum` member, which is accepted in place of a string
+    /// (members of `str` subclasses such as `enum.StrEnum` are strings already)
+    fn enum_member_name(&self) -> Result<Option<Bound<'py, PyString>>> {
+        static ENUM: PyOnceLock<Py<PyType>> = PyOnceLock::new();
+
+        let obj = self.input();
+        if obj.is_instance_of::<PyString>() {
+            return Ok(None);
+        }
+        let py = obj.py();
+        if !obj.is_instance(ENUM.import(py, "enum", "Enum")?)? {
+            return Ok(None);
+        }
+        Ok(Some(obj.getattr(intern!(py, "name"))?.cast_into()?))
+    }
+
+    /// `input` formatted as by `json.dumps`, if it is a mapping key which should be stringified
+    /// (see [`with_stringified_keys`](Self::with_stringified_keys))
+    fn stringified_key(&self) -> Result<Option<String>> {
+        let obj = self.input();
+        if !(self.key && self.options.stringify_keys) || obj.is_instance_of::<PyString>() {
+            return Ok(None);
+        }
+        let s = if let Some(name) = self.enum_member_name()? {
+            name.to_cow()?.into_owned()
+        } else if obj.is_none() {
+            "null".to_string()
+        } else if let Ok(b) = obj.cast::<PyBool>() {
+            b.is_true().to_string()
+        } else if obj.is_instance_of::<PyInt>() || obj.is_instance_of::<PyFloat>() {
+            // Use the builtin `__repr__`, rather than that of subclasses
+            let py = obj.py();
+            let builtin = if obj.is_instance_of::<PyInt>() {
+                py.get_type::<PyInt>()
+            } else {
+                py.get_type::<PyFloat>()
+            };
+            builtin
+                .call_method1(intern!(py, "__repr__"), (obj,))?
+                .extract()?
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(s))
+    }
+
     fn deserialize_any_int<'de, V>(&self, int: &Bound<'_, PyInt>, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
@@ -124,19 +483,23 @@ macro_rules! deserialize_type {
         where
             V: de::Visitor<'de>,
         {
-            visitor.$visit(self.input.extract()?)
+            visitor.$visit(self.input().extract()?)
         }
     };
 }
 
-impl<'de> de::Deserializer<'de> for &'_ mut Depythonizer<'_, '_> {
+impl<'de> de::Deserializer<'de> for &'_ mut Depythonizer<'de, '_> {
     type Error = PythonizeError;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        let obj = self.input;
+        let obj = self.input();
+
+        if let Some(key) = self.stringified_key()? {
+            return visitor.visit_string(key);
+        }
 
         // First check for cases which are cheap to check due to pointer
         // comparison or bitflag checks
@@ -147,7 +510,8 @@ impl<'de> de::Deserializer<'de> for &'_ mut Depythonizer<'_, '_> {
         } else if let Ok(x) = obj.cast::<PyInt>() {
             self.deserialize_any_int(x, visitor)
         } else if obj.is_instance_of::<PyList>() || obj.is_instance_of::<PyTuple>() {
-            self.deserialize_tuple(obj.len()?, visitor)
+            let len = obj.len()?;
+            self.deserialize_tuple(len, visitor)
         } else if obj.is_instance_of::<PyDict>() {
             self.deserialize_map(visitor)
         } else if obj.is_instance_of::<PyString>() {
@@ -155,19 +519,30 @@ impl<'de> de::Deserializer<'de> for &'_ mut Depythonizer<'_, '_> {
         }
         // Continue with cases which are slower to check because they go
         // through `isinstance` machinery
-        else if obj.is_instance_of::<PyBytes>() || obj.is_instance_of::<PyByteArray>() {
+        else if obj.is_instance_of::<PyBytes>()
+            || obj.is_instance_of::<PyByteArray>()
+            || is_byte_memoryview(obj)?
+        {
             self.deserialize_bytes(visitor)
         } else if obj.is_instance_of::<PyFloat>() {
             self.deserialize_f64(visitor)
         } else if obj.is_instance_of::<PyFrozenSet>() || obj.is_instance_of::<PySet>() {
             self.deserialize_seq(visitor)
         } else if obj.cast::<PySequence>().is_ok() {
-            self.deserialize_tuple(obj.len()?, visitor)
+            let len = obj.len()?;
+            self.deserialize_tuple(len, visitor)
         } else if obj.cast::<PyMapping>().is_ok() {
             self.deserialize_map(visitor)
         } else if let Some(dc) = DataclassCandidate::try_new(obj) {
-            visitor.visit_map(PyDataclassAccess::new(dc)?)
+            visitor.visit_map(PyDataclassAccess::new(dc, self.options)?)
         } else {
+            #[cfg(feature = "numpy")]
+            if let Some(obj) = crate::numpy::to_builtin(obj)? {
+                return Depythonizer::from_owned(obj, self.options).deserialize_any(visitor);
+            }
+            if let Some(iter) = self.iterable_access()? {
+                return visitor.visit_seq(iter);
+            }
             Err(obj.get_type().qualname().map_or_else(
                 |_| PythonizeError::unsupported_type("unknown"),
                 PythonizeError::unsupported_type,
@@ -179,14 +554,14 @@ impl<'de> de::Deserializer<'de> for &'_ mut Depythonizer<'_, '_> {
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_bool(self.input.is_truthy()?)
+        visitor.visit_bool(self.input().is_truthy()?)
     }
 
     fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        let s = self.input.cast::<PyString>()?.to_cow()?;
+        let s = self.input().cast::<PyString>()?.to_cow()?;
         if s.len() != 1 {
             return Err(PythonizeError::invalid_length_char());
         }
@@ -210,8 +585,21 @@ impl<'de> de::Deserializer<'de> for &'_ mut Depythonizer<'_, '_> {
     where
         V: de::Visitor<'de>,
     {
-        let s = self.input.cast::<PyString>()?;
-        visitor.visit_str(&s.to_cow()?)
+        if !self.input().is_instance_of::<PyString>() {
+            if let Some(name) = self.enum_member_name()? {
+                return visitor.visit_str(&name.to_cow()?);
+            }
+            if let Some(key) = self.stringified_key()? {
+                return visitor.visit_string(key);
+            }
+        }
+        match &self.input {
+            Input::Borrowed(obj) => match obj.cast::<PyString>()?.extract::<Cow<'de, str>>()? {
+                Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+                Cow::Owned(s) => visitor.visit_string(s),
+            },
+            Input::Owned(obj) => visitor.visit_str(&obj.cast::<PyString>()?.to_cow()?),
+        }
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
@@ -225,8 +613,26 @@ impl<'de> de::Deserializer<'de> for &'_ mut Depythonizer<'_, '_> {
     where
         V: de::Visitor<'de>,
     {
-        let b = self.input.cast::<PyBytes>()?;
-        visitor.visit_bytes(b.as_bytes())
+        let obj = self.input();
+        let err = match obj.cast::<PyBytes>() {
+            Ok(b) => {
+                return match self.input {
+                    Input::Borrowed(obj) => {
+                        visitor.visit_borrowed_bytes(obj.cast::<PyBytes>()?.extract::<&'de [u8]>()?)
+                    }
+                    Input::Owned(_) => visitor.visit_bytes(b.as_bytes()),
+                }
+            }
+            Err(err) => err,
+        };
+
+        if let Ok(b) = obj.cast::<PyByteArray>() {
+            visitor.visit_byte_buf(b.to_vec())
+        } else if let Some(b) = buffer_to_vec(obj)? {
+            visitor.visit_byte_buf(b)
+        } else {
+            Err(err.into())
+        }
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
@@ -240,7 +646,7 @@ impl<'de> de::Deserializer<'de> for &'_ mut Depythonizer<'_, '_> {
     where
         V: de::Visitor<'de>,
     {
-        if self.input.is_none() {
+        if self.input().is_none() {
             visitor.visit_none()
         } else {
             visitor.visit_some(self)
@@ -251,7 +657,7 @@ impl<'de> de::Deserializer<'de> for &'_ mut Depythonizer<'_, '_> {
     where
         V: de::Visitor<'de>,
     {
-        if self.input.is_none() {
+        if self.input().is_none() {
             visitor.visit_unit()
         } else {
             Err(PythonizeError::msg("expected None"))
@@ -265,10 +671,46 @@ impl<'de> de::Deserializer<'de> for &'_ mut Depythonizer<'_, '_> {
         self.deserialize_unit(visitor)
     }
 
-    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
+        if name == OBJECT_TOKEN {
+            let obj = self.input().to_owned().unbind();
+            return object::with_deserialized(obj, || visitor.visit_newtype_struct(self));
+        }
+        if name == VALUE_TOKEN {
+            let obj = self.input();
+            let variant = if obj.is_instance_of::<PyTuple>() {
+                Some("Tuple")
+            } else if obj.is_instance_of::<PySet>() || obj.is_instance_of::<PyFrozenSet>() {
+                Some("Set")
+            } else if let Ok(int) = obj.cast::<PyInt>() {
+                (int.extract::<i128>().is_err() && int.extract::<u128>().is_err()).then_some("Int")
+            } else {
+                None
+            };
+            // Tag the values which serde's data model can't tell apart, see `Value`
+            if let Some(variant) = variant {
+                let value = match variant {
+                    "Int" => obj.str()?.into_any(),
+                    _ => obj.to_owned(),
+                };
+                let variant = PyString::new(obj.py(), variant);
+                return visitor.visit_enum(PyEnumAccess::new(value, variant, self.options));
+            }
+        }
+        if name == as_::DECIMAL_TOKEN {
+            let py = self.input().py();
+            let decimal = py
+                .import(intern!(py, "decimal"))?
+                .getattr(intern!(py, "Decimal"))?;
+            if self.input().is_instance(&decimal)? {
+                return visitor.visit_newtype_struct(PyDecimalDeserializer {
+                    decimal: self.input().to_owned(),
+                });
+            }
+        }
         visitor.visit_newtype_struct(self)
     }
 
@@ -276,6 +718,21 @@ impl<'de> de::Deserializer<'de> for &'_ mut Depythonizer<'_, '_> {
     where
         V: de::Visitor<'de>,
     {
+        // Read the contents of `bytes` directly, e.g. for `Vec<u8>`
+        if let Ok(bytes) = self.input().cast::<PyBytes>() {
+            let mut seq =
+                SeqDeserializer::<_, PythonizeError>::new(bytes.as_bytes().iter().copied());
+            let value = visitor.visit_seq(&mut seq)?;
+            seq.end()?;
+            return Ok(value);
+        }
+
+        // The buffer protocol is only part of the limited API from Python 3.11
+        #[cfg(all(feature = "numpy", any(not(Py_LIMITED_API), Py_3_11)))]
+        if let Some(buffer) = crate::numpy::PyNumericBufferAccess::new(self.input())? {
+            return visitor.visit_seq(buffer);
+        }
+
         match self.sequence_access(None) {
             Ok(seq) => visitor.visit_seq(seq),
             Err(e) => {
@@ -284,6 +741,14 @@ impl<'de> de::Deserializer<'de> for &'_ mut Depythonizer<'_, '_> {
                     if let Ok(set) = self.set_access() {
                         return visitor.visit_seq(set);
                     }
+                    #[cfg(feature = "numpy")]
+                    if let Some(obj) = crate::numpy::to_builtin(self.input())? {
+                        return Depythonizer::from_owned(obj, self.options)
+                            .deserialize_seq(visitor);
+                    }
+                    if let Some(iter) = self.iterable_access()? {
+                        return visitor.visit_seq(iter);
+                    }
                 }
                 Err(e)
             }
@@ -313,23 +778,23 @@ impl<'de> de::Deserializer<'de> for &'_ mut Depythonizer<'_, '_> {
     where
         V: de::Visitor<'de>,
     {
-        if let Some(dc_access) = self.dataclass_access()? {
-            visitor.visit_map(dc_access)
-        } else {
-            visitor.visit_map(self.dict_access()?)
-        }
+        self.visit_mapping(&[], visitor)
     }
 
     fn deserialize_struct<V>(
         self,
         _name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        self.deserialize_map(visitor)
+        if let Some(nt_access) = self.namedtuple_access()? {
+            visitor.visit_map(nt_access.with_fields(fields))
+        } else {
+            self.visit_mapping(fields, visitor)
+        }
     }
 
     fn deserialize_enum<V>(
@@ -341,9 +806,11 @@ impl<'de> de::Deserializer<'de> for &'_ mut Depythonizer<'_, '_> {
     where
         V: de::Visitor<'de>,
     {
-        let item = &self.input;
+        let item = self.input();
         if let Ok(s) = item.cast::<PyString>() {
             visitor.visit_enum(s.to_cow()?.into_deserializer())
+        } else if let Some(name) = self.enum_member_name()? {
+            visitor.visit_enum(name.to_cow()?.into_deserializer())
         } else if let Ok(m) = item.cast::<PyMapping>() {
             // Get the enum variant from the mapping key
             if m.len()? != 1 {
@@ -355,7 +822,7 @@ impl<'de> de::Deserializer<'de> for &'_ mut Depythonizer<'_, '_> {
                 .cast_into::<PyString>()
                 .map_err(|_| PythonizeError::dict_key_not_string())?;
             let value = m.get_item(&variant)?;
-            visitor.visit_enum(PyEnumAccess::new(&value, variant))
+            visitor.visit_enum(PyEnumAccess::new(value, variant, self.options))
         } else {
             Err(PythonizeError::invalid_enum_type())
         }
@@ -365,11 +832,14 @@ impl<'de> de::Deserializer<'de> for &'_ mut Depythonizer<'_, '_> {
     where
         V: de::Visitor<'de>,
     {
-        let s = self
-            .input
-            .cast::<PyString>()
-            .map_err(|_| PythonizeError::dict_key_not_string())?;
-        visitor.visit_str(&s.to_cow()?)
+        let obj = self.input();
+        if let Ok(s) = obj.cast::<PyString>() {
+            visitor.visit_str(&s.to_cow()?)
+        } else if let Some(name) = self.enum_member_name()? {
+            visitor.visit_str(&name.to_cow()?)
+        } else {
+            Err(PythonizeError::dict_key_not_string())
+        }
     }
 
     fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
@@ -380,19 +850,158 @@ impl<'de> de::Deserializer<'de> for &'_ mut Depythonizer<'_, '_> {
     }
 }
 
+/// Merge the entries of `patch` into `base` if both are mappings, recursively, or otherwise
+/// replace `base` with `patch`. Enum variants are always replaced, so that a patch can switch to
+/// another variant, and the fields of structs may be named in `field_case` or as they are. Only
+/// dicts created by `depythonize_into` are modified.
+fn merge_into<'py>(
+    base: Bound<'py, PyAny>,
+    patch: &Bound<'py, PyAny>,
+    field_case: FieldCase,
+) -> Result<Bound<'py, PyAny>> {
+    let (Ok(dict), Ok(entries)) = (base.cast::<PyDict>(), patch.cast::<PyMapping>()) else {
+        return Ok(patch.clone());
+    };
+    let marked = Marked::of(&base)?;
+    if marked == Marked::Variant {
+        return Ok(patch.clone());
+    }
+    for item in entries.items()?.iter() {
+        let (mut key, value) = item.extract::<(Bound<'py, PyAny>, Bound<'py, PyAny>)>()?;
+        // `base` names fields in `field_case`, so that a field is not given twice
+        if marked == Marked::Struct && !dict.contains(&key)? {
+            if let Ok(name) = key.cast::<PyString>() {
+                let field = PyString::new(key.py(), &field_case.apply(&name.to_cow()?));
+                if dict.contains(&field)? {
+                    key = field.into_any();
+                }
+            }
+        }
+        let value = match dict.get_item(&key)? {
+            Some(current) => merge_into(current, &value, field_case)?,
+            None => value,
+        };
+        dict.set_item(key, value)?;
+    }
+    Ok(base)
+}
+
+/// The items of a Python sequence
+enum PySequenceItems<'a, 'py> {
+    /// Items of a tuple living for `'a`, which can be borrowed for `'a` too
+    Tuple(Borrowed<'a, 'py, PyTuple>),
+    Sequence(Bound<'py, PySequence>),
+}
+
+impl<'a, 'py> PySequenceItems<'a, 'py> {
+    fn py(&self) -> Python<'py> {
+        match self {
+            PySequenceItems::Tuple(tuple) => tuple.py(),
+            PySequenceItems::Sequence(seq) => seq.py(),
+        }
+    }
+
+    fn get(&self, index: usize, options: DepythonizerOptions) -> Result<Depythonizer<'a, 'py>> {
+        match self {
+            PySequenceItems::Tuple(tuple) => {
+                // SAFETY: a tuple holds a strong reference to each of its items and cannot be
+                // mutated, so the item lives for as long as the tuple, which is at least `'a`.
+                let item = unsafe {
+                    Borrowed::from_ptr_or_err(
+                        tuple.py(),
+                        ffi::PyTuple_GetItem(tuple.as_ptr(), index as ffi::Py_ssize_t),
+                    )?
+                };
+                Ok(Depythonizer::from_borrowed(item, options))
+            }
+            PySequenceItems::Sequence(seq) => {
+                Ok(Depythonizer::from_owned(seq.get_item(index)?, options))
+            }
+        }
+    }
+}
+
+/// Copy the contents of an object exporting the buffer protocol (such as a `memoryview`), if it
+/// does so with a contiguous buffer of bytes (`B`, `b` or `c` items).
+#[cfg(any(not(Py_LIMITED_API), Py_3_11))]
+fn buffer_to_vec(obj: &Bound<'_, PyAny>) -> Result<Option<Vec<u8>>> {
+    use pyo3::buffer::{PyBuffer, PyUntypedBuffer};
+
+    let Ok(buffer) = PyUntypedBuffer::get(obj) else {
+        return Ok(None);
+    };
+    if buffer.item_size() != 1
+        || !is_byte_format(&buffer.format().to_string_lossy())
+        || !buffer.is_c_contiguous()
+    {
+        return Ok(None);
+    }
+    // `b` items are read as `u8` too, which only differ in how they are interpreted
+    let buffer = match buffer.into_typed::<u8>() {
+        Ok(buffer) => buffer,
+        Err(_) => PyBuffer::<u8>::get(
+            &PyMemoryView::from(obj)?
+                .call_method1(intern!(obj.py(), "cast"), (intern!(obj.py(), "B"),))?,
+        )?,
+    };
+    Ok(Some(buffer.to_vec(obj.py())?))
+}
+
+/// Copy the contents of an object exporting the buffer protocol (such as a `memoryview`), if it
+/// does so with a contiguous buffer of bytes (`B`, `b` or `c` items).
+#[cfg(all(Py_LIMITED_API, not(Py_3_11)))]
+fn buffer_to_vec(obj: &Bound<'_, PyAny>) -> Result<Option<Vec<u8>>> {
+    let Ok(view) = PyMemoryView::from(obj) else {
+        return Ok(None);
+    };
+    let py = obj.py();
+    let itemsize: usize = view.getattr(intern!(py, "itemsize"))?.extract()?;
+    let format = view.getattr(intern!(py, "format"))?;
+    if itemsize != 1
+        || !is_byte_format(&format.cast::<PyString>()?.to_cow()?)
+        || !view.getattr(intern!(py, "c_contiguous"))?.is_truthy()?
+    {
+        return Ok(None);
+    }
+    let bytes = view.call_method0(intern!(py, "tobytes"))?;
+    Ok(Some(bytes.cast::<PyBytes>()?.as_bytes().to_vec()))
+}
+
+/// Whether `obj` is a `memoryview` of bytes, rather than of other items which are read as a
+/// sequence
+fn is_byte_memoryview(obj: &Bound<'_, PyAny>) -> Result<bool> {
+    if !obj.is_instance_of::<PyMemoryView>() {
+        return Ok(false);
+    }
+    let format = obj.getattr(intern!(obj.py(), "format"))?;
+    Ok(is_byte_format(&format.cast::<PyString>()?.to_cow()?))
+}
+
+/// Whether a buffer format describes single bytes, with an optional byte order prefix
+fn is_byte_format(format: &str) -> bool {
+    let format = format.trim_start_matches(['@', '=', '<', '>', '!']);
+    matches!(format, "B" | "b" | "c")
+}
+
 struct PySequenceAccess<'a, 'py> {
-    seq: &'a Bound<'py, PySequence>,
+    items: PySequenceItems<'a, 'py>,
     index: usize,
     len: usize,
+    options: DepythonizerOptions,
 }
 
 impl<'a, 'py> PySequenceAccess<'a, 'py> {
-    fn new(seq: &'a Bound<'py, PySequence>, len: usize) -> Self {
-        Self { seq, index: 0, len }
+    fn new(items: PySequenceItems<'a, 'py>, len: usize, options: DepythonizerOptions) -> Self {
+        Self {
+            items,
+            index: 0,
+            len,
+            options,
+        }
     }
 }
 
-impl<'de> de::SeqAccess<'de> for PySequenceAccess<'_, '_> {
+impl<'de> de::SeqAccess<'de> for PySequenceAccess<'de, '_> {
     type Error = PythonizeError;
 
     fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
@@ -400,35 +1009,84 @@ impl<'de> de::SeqAccess<'de> for PySequenceAccess<'_, '_> {
         T: de::DeserializeSeed<'de>,
     {
         if self.index < self.len {
-            let item = self.seq.get_item(self.index)?;
+            let mut item = self.items.get(self.index, self.options)?;
             self.index += 1;
-            seed.deserialize(&mut Depythonizer::from_object(&item))
-                .map(Some)
+            seed.deserialize(&mut item).map(Some)
         } else {
             Ok(None)
         }
     }
 }
 
-struct PySetAsSequence<'py> {
+/// An iterator over the items of a Python object which converts each of them to `T`, created by
+/// [`depythonize_iter`] and [`Depythonizer::iter`]
+pub struct DepythonizeIter<'a, 'py, T> {
+    items: Items<'a, 'py>,
+    index: usize,
+    _type: PhantomData<fn() -> T>,
+}
+
+enum Items<'a, 'py> {
+    Sequence(PySequenceAccess<'a, 'py>),
+    Iterator(PyIterableAccess<'py>),
+}
+
+impl<'a, T> Iterator for DepythonizeIter<'a, '_, T>
+where
+    T: Deserialize<'a>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        use de::SeqAccess;
+
+        let (py, item) = match &mut self.items {
+            Items::Sequence(seq) => (seq.items.py(), seq.next_element()),
+            Items::Iterator(iter) => (iter.iter.py(), iter.next_element()),
+        };
+        let index = self.index;
+        self.index += 1;
+        item.map_err(|e| PythonizeError::invalid_item(py, index, e))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.items {
+            Items::Sequence(seq) => {
+                let remaining = seq.len.saturating_sub(seq.index);
+                (remaining, Some(remaining))
+            }
+            Items::Iterator(_) => (0, None),
+        }
+    }
+}
+
+struct PyIterableAccess<'py> {
     iter: Bound<'py, PyIterator>,
+    options: DepythonizerOptions,
 }
 
-impl<'py> PySetAsSequence<'py> {
-    fn from_set(set: &Bound<'py, PySet>) -> Self {
+impl<'py> PyIterableAccess<'py> {
+    fn from_set(set: &Bound<'py, PySet>, options: DepythonizerOptions) -> Self {
         Self {
             iter: PyIterator::from_object(set).expect("set is always iterable"),
+            options,
         }
     }
 
-    fn from_frozenset(set: &Bound<'py, PyFrozenSet>) -> Self {
+    fn from_frozenset(set: &Bound<'py, PyFrozenSet>, options: DepythonizerOptions) -> Self {
         Self {
             iter: PyIterator::from_object(set).expect("frozenset is always iterable"),
+            options,
         }
     }
+
+    fn from_iterator(iter: Bound<'py, PyIterator>, options: DepythonizerOptions) -> Self {
+        Self { iter, options }
+    }
 }
 
-impl<'de> de::SeqAccess<'de> for PySetAsSequence<'_> {
+impl<'de> de::SeqAccess<'de> for PyIterableAccess<'_> {
     type Error = PythonizeError;
 
     fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
@@ -437,37 +1095,67 @@ impl<'de> de::SeqAccess<'de> for PySetAsSequence<'_> {
     {
         match self.iter.next() {
             Some(item) => seed
-                .deserialize(&mut Depythonizer::from_object(&item?))
+                .deserialize(&mut Depythonizer::from_owned(item?, self.options))
                 .map(Some),
             None => Ok(None),
         }
     }
 }
 
-struct PyMappingAccess<'py> {
+struct PyMappingAccess<'a, 'py> {
     keys: Bound<'py, PyList>,
-    values: Bound<'py, PyList>,
+    values: PySequenceItems<'a, 'py>,
     key_idx: usize,
     val_idx: usize,
     len: usize,
+    options: DepythonizerOptions,
+    /// The fields of the struct being deserialized, if any
+    fields: &'static [&'static str],
 }
 
-impl<'py> PyMappingAccess<'py> {
-    fn new(map: &Bound<'py, PyMapping>) -> Result<Self> {
+impl<'a, 'py> PyMappingAccess<'a, 'py> {
+    fn new(map: &Bound<'py, PyMapping>, options: DepythonizerOptions) -> Result<Self> {
         let keys = map.keys()?;
         let values = map.values()?;
         let len = map.len()?;
         Ok(Self {
             keys,
+            values: PySequenceItems::Sequence(values.into_sequence()),
+            key_idx: 0,
+            val_idx: 0,
+            len,
+            options,
+            fields: &[],
+        })
+    }
+
+    fn from_namedtuple(
+        fields: &Bound<'py, PyTuple>,
+        values: PySequenceItems<'a, 'py>,
+        values_len: usize,
+        options: DepythonizerOptions,
+    ) -> Result<Self> {
+        let len = fields.len();
+        if values_len != len {
+            return Err(PythonizeError::incorrect_sequence_length(len, values_len));
+        }
+        Ok(Self {
+            keys: fields.to_list(),
             values,
             key_idx: 0,
             val_idx: 0,
             len,
+            options,
+            fields: &[],
         })
     }
+
+    fn with_fields(self, fields: &'static [&'static str]) -> Self {
+        Self { fields, ..self }
+    }
 }
 
-impl<'de> de::MapAccess<'de> for PyMappingAccess<'_> {
+impl<'de> de::MapAccess<'de> for PyMappingAccess<'de, '_> {
     type Error = PythonizeError;
 
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
@@ -477,7 +1165,8 @@ impl<'de> de::MapAccess<'de> for PyMappingAccess<'_> {
         if self.key_idx < self.len {
             let item = self.keys.get_item(self.key_idx)?;
             self.key_idx += 1;
-            seed.deserialize(&mut Depythonizer::from_object(&item))
+            let item = self.options.field_key(item, self.fields)?;
+            seed.deserialize(&mut Depythonizer::from_key(item, self.options))
                 .map(Some)
         } else {
             Ok(None)
@@ -488,9 +1177,9 @@ impl<'de> de::MapAccess<'de> for PyMappingAccess<'_> {
     where
         V: de::DeserializeSeed<'de>,
     {
-        let item = self.values.get_item(self.val_idx)?;
+        let mut item = self.values.get(self.val_idx, self.options)?;
         self.val_idx += 1;
-        seed.deserialize(&mut Depythonizer::from_object(&item))
+        seed.deserialize(&mut item)
     }
 }
 
@@ -514,12 +1203,15 @@ struct PyDataclassAccess<'py> {
     fields: Bound<'py, PyList>,
     dict: Bound<'py, PyDict>,
     field_idx: usize,
-    val_idx: usize,
+    /// The value of the field returned by the last call to `next_key_seed`
+    value: Option<Bound<'py, PyAny>>,
     len: usize,
+    options: DepythonizerOptions,
+    fields_of_struct: &'static [&'static str],
 }
 
 impl<'py> PyDataclassAccess<'py> {
-    fn new(dc: DataclassCandidate<'_, 'py>) -> Result<Self> {
+    fn new(dc: DataclassCandidate<'_, 'py>, options: DepythonizerOptions) -> Result<Self> {
         let fields = dc.fields.cast::<PyDict>()?.keys();
         let dict = dc
             .obj
@@ -530,10 +1222,19 @@ impl<'py> PyDataclassAccess<'py> {
             fields,
             dict,
             field_idx: 0,
-            val_idx: 0,
+            value: None,
             len,
+            options,
+            fields_of_struct: &[],
         })
     }
+
+    fn with_fields(self, fields: &'static [&'static str]) -> Self {
+        Self {
+            fields_of_struct: fields,
+            ..self
+        }
+    }
 }
 
 impl<'de> de::MapAccess<'de> for PyDataclassAccess<'_> {
@@ -543,27 +1244,32 @@ impl<'de> de::MapAccess<'de> for PyDataclassAccess<'_> {
     where
         K: de::DeserializeSeed<'de>,
     {
-        if self.field_idx < self.len {
+        while self.field_idx < self.len {
             let item = self.fields.get_item(self.field_idx)?;
             self.field_idx += 1;
-            seed.deserialize(&mut Depythonizer::from_object(&item))
-                .map(Some)
-        } else {
-            Ok(None)
+            // Fields which are not set on the instance (e.g. declared with `init=False`) are
+            // absent, so that serde either fills in a default or reports the missing field
+            let Some(value) = self.dict.get_item(&item)? else {
+                continue;
+            };
+            self.value = Some(value);
+            let item = self.options.field_key(item, self.fields_of_struct)?;
+            return seed
+                .deserialize(&mut Depythonizer::from_key(item, self.options))
+                .map(Some);
         }
+        Ok(None)
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
     where
         V: de::DeserializeSeed<'de>,
     {
-        let key = self.fields.get_item(self.val_idx)?;
         let value = self
-            .dict
-            .get_item(&key)?
-            .ok_or_else(|| PyKeyError::new_err(key.unbind()))?;
-        self.val_idx += 1;
-        seed.deserialize(&mut Depythonizer::from_object(&value))
+            .value
+            .take()
+            .expect("next_value_seed should always be called after next_key_seed");
+        seed.deserialize(&mut Depythonizer::from_owned(value, self.options))
     }
 }
 
@@ -573,15 +1279,19 @@ struct PyEnumAccess<'a, 'py> {
 }
 
 impl<'a, 'py> PyEnumAccess<'a, 'py> {
-    fn new(obj: &'a Bound<'py, PyAny>, variant: Bound<'py, PyString>) -> Self {
+    fn new(
+        obj: Bound<'py, PyAny>,
+        variant: Bound<'py, PyString>,
+        options: DepythonizerOptions,
+    ) -> Self {
         Self {
-            de: Depythonizer::from_object(obj),
+            de: Depythonizer::from_owned(obj, options),
             variant,
         }
     }
 }
 
-impl<'de> de::EnumAccess<'de> for PyEnumAccess<'_, '_> {
+impl<'de> de::EnumAccess<'de> for PyEnumAccess<'de, '_> {
     type Error = PythonizeError;
     type Variant = Self;
 
@@ -596,7 +1306,7 @@ impl<'de> de::EnumAccess<'de> for PyEnumAccess<'_, '_> {
     }
 }
 
-impl<'de> de::VariantAccess<'de> for PyEnumAccess<'_, '_> {
+impl<'de> de::VariantAccess<'de> for PyEnumAccess<'de, '_> {
     type Error = PythonizeError;
 
     fn unit_variant(self) -> Result<()> {
@@ -617,11 +1327,92 @@ impl<'de> de::VariantAccess<'de> for PyEnumAccess<'_, '_> {
         visitor.visit_seq(self.de.sequence_access(Some(len))?)
     }
 
-    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_map(self.de.dict_access()?.with_fields(fields))
+    }
+}
+
+/// Presents a `decimal.Decimal` read with [`as_::decimal`](crate::as_::decimal) as its string
+/// representation, or as a float or integer when one is requested
+struct PyDecimalDeserializer<'py> {
+    decimal: Bound<'py, PyAny>,
+}
+
+impl<'py> PyDecimalDeserializer<'py> {
+    /// Convert the decimal to an `int`, failing if it has a fractional part
+    fn integer(&self, expected: &dyn de::Expected) -> Result<Bound<'py, PyAny>> {
+        let py = self.decimal.py();
+        let integral = self
+            .decimal
+            .call_method0(intern!(py, "to_integral_value"))?;
+        if !self.decimal.eq(&integral)? {
+            let decimal = self.decimal.str()?;
+            return Err(de::Error::invalid_value(
+                de::Unexpected::Other(&format!("decimal {}", decimal.to_cow()?)),
+                expected,
+            ));
+        }
+        Ok(integral.call_method0(intern!(py, "__int__"))?)
+    }
+}
+
+macro_rules! deserialize_decimal_integer {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: de::Visitor<'de>,
+            {
+                let int = self.integer(&visitor)?;
+                visitor.$visit(int.extract()?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for PyDecimalDeserializer<'_> {
+    type Error = PythonizeError;
+
+    deserialize_decimal_integer! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+    }
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_string(self.decimal.str()?.to_cow()?.into_owned())
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_map(self.de.dict_access()?)
+        visitor.visit_f64(self.decimal.extract()?)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool char str string bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
     }
 }
 
@@ -659,8 +1450,10 @@ mod test {
 
     use super::*;
     use crate::error::ErrorImpl;
+    use crate::FieldCase;
     use maplit::hashmap;
-    use pyo3::{IntoPyObject, Python};
+    use pyo3::{IntoPyObject, PyErr, Python};
+    use serde::Serialize;
     use serde_json::{json, Value as JsonValue};
 
     fn test_de<T>(code: &CStr, expected: &T, expected_json: &JsonValue)
@@ -829,12 +1622,585 @@ mod test {
     }
 
     #[test]
-    fn test_hashmap() {
-        let expected = hashmap! {"foo".to_string() => 4};
-        let expected_json = json!({"foo": 4 });
-        let code = c"{'foo': 4}";
-        test_de(code, &expected, &expected_json);
-    }
+    fn test_vec_from_iterables() {
+        Python::attach(|py| {
+            let obj = py.eval(c"(x * 2 for x in range(3))", None, None).unwrap();
+            assert!(depythonize::<Vec<i32>>(&obj).is_err());
+
+            let mut de = Depythonizer::from_object(&obj).with_iterables(true);
+            assert_eq!(Vec::<i32>::deserialize(&mut de).unwrap(), vec![0, 2, 4]);
+
+            let obj = py.eval(c"map(str, [1, 2])", None, None).unwrap();
+            let mut de = Depythonizer::from_object(&obj).with_iterables(true);
+            assert_eq!(JsonValue::deserialize(&mut de).unwrap(), json!(["1", "2"]));
+
+            // Nested iterables are accepted too
+            let obj = py.eval(c"{'a': iter([(1, 2)])}", None, None).unwrap();
+            let mut de = Depythonizer::from_object(&obj).with_iterables(true);
+            let map = HashMap::<String, Vec<Vec<i32>>>::deserialize(&mut de).unwrap();
+            assert_eq!(map["a"], vec![vec![1, 2]]);
+
+            // Mappings are not sequences of their keys
+            let obj = py.eval(c"{'a': 1}", None, None).unwrap();
+            let mut de = Depythonizer::from_object(&obj).with_iterables(true);
+            assert!(Vec::<String>::deserialize(&mut de).is_err());
+        });
+    }
+
+    #[test]
+    fn test_tuple_from_iterable() {
+        Python::attach(|py| {
+            let obj = py.eval(c"iter([1, 'a'])", None, None).unwrap();
+            let mut de = Depythonizer::from_object(&obj).with_iterables(true);
+            let tuple = <(i32, String)>::deserialize(&mut de).unwrap();
+            assert_eq!(tuple, (1, "a".to_string()));
+
+            let obj = py.eval(c"iter([1, 2, 3])", None, None).unwrap();
+            let mut de = Depythonizer::from_object(&obj).with_iterables(true);
+            let err = <(i32, i32)>::deserialize(&mut de).unwrap_err();
+            assert_eq!(err.to_string(), "expected sequence of length 2, got 3");
+
+            // Only one item more than expected is read
+            let obj = py
+                .eval(c"__import__('itertools').count()", None, None)
+                .unwrap();
+            let mut de = Depythonizer::from_object(&obj).with_iterables(true);
+            let err = <(i32, i32)>::deserialize(&mut de).unwrap_err();
+            assert_eq!(err.to_string(), "expected sequence of length 2, got 3");
+            assert_eq!(
+                obj.call_method0("__next__")
+                    .unwrap()
+                    .extract::<i32>()
+                    .unwrap(),
+                3
+            );
+
+            let obj = py.eval(c"(1 / x for x in [1, 2, 0])", None, None).unwrap();
+            let mut de = Depythonizer::from_object(&obj).with_iterables(true);
+            let err = <(f64,)>::deserialize(&mut de).unwrap_err();
+            assert_eq!(err.to_string(), "expected sequence of length 1, got 2");
+        });
+    }
+
+    #[test]
+    fn test_iterable_error() {
+        Python::attach(|py| {
+            let locals = pyo3::types::PyDict::new(py);
+            py.run(
+                c"def items():\n    yield 1\n    raise ValueError('oops')",
+                None,
+                Some(&locals),
+            )
+            .unwrap();
+            let obj = py.eval(c"items()", None, Some(&locals)).unwrap();
+            let mut de = Depythonizer::from_object(&obj).with_iterables(true);
+            let err = Vec::<i32>::deserialize(&mut de).unwrap_err();
+            assert_eq!(err.to_string(), "ValueError: oops");
+        });
+    }
+
+    #[test]
+    fn test_field_case() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Request {
+            user_id: u32,
+            retry_policy: Policy,
+            extra_tags: HashMap<String, u8>,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        enum Policy {
+            Backoff { max_delay: u32 },
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct BackoffFields {
+            max_delay: u32,
+        }
+
+        Python::attach(|py| {
+            let expected = Request {
+                user_id: 1,
+                retry_policy: Policy::Backoff { max_delay: 5 },
+                extra_tags: hashmap! {"someTag".to_string() => 1},
+            };
+
+            let obj = py
+                .eval(
+                    c"{'userId': 1, 'retryPolicy': {'Backoff': {'maxDelay': 5}}, 'extraTags': {'someTag': 1}}",
+                    None,
+                    None,
+                )
+                .unwrap();
+            assert!(depythonize::<Request>(&obj).is_err());
+            let mut de = Depythonizer::from_object(&obj).with_field_case(FieldCase::CamelCase);
+            assert_eq!(Request::deserialize(&mut de).unwrap(), expected);
+
+            // Names of the Rust fields are accepted too
+            let obj = py
+                .eval(
+                    c"{'user-id': 1, 'retry_policy': {'Backoff': {'max-delay': 5}}, 'extra-tags': {'someTag': 1}}",
+                    None,
+                    None,
+                )
+                .unwrap();
+            let mut de = Depythonizer::from_object(&obj).with_field_case(FieldCase::KebabCase);
+            assert_eq!(Request::deserialize(&mut de).unwrap(), expected);
+
+            let locals = pyo3::types::PyDict::new(py);
+            py.run(
+                c"import dataclasses\n@dataclasses.dataclass\nclass Backoff:\n    MaxDelay: int",
+                None,
+                Some(&locals),
+            )
+            .unwrap();
+            let obj = py
+                .eval(c"{'Backoff': Backoff(5)}", None, Some(&locals))
+                .unwrap();
+            let mut de = Depythonizer::from_object(&obj).with_field_case(FieldCase::PascalCase);
+            assert_eq!(
+                HashMap::<String, BackoffFields>::deserialize(&mut de).unwrap()["Backoff"],
+                BackoffFields { max_delay: 5 }
+            );
+        });
+    }
+
+    #[test]
+    fn test_field_case_limits() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Flattened {
+            user_id: u32,
+            #[serde(flatten)]
+            extra: Extra,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Extra {
+            max_delay: u32,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(tag = "type")]
+        enum Tagged {
+            Backoff { max_delay: u32 },
+        }
+
+        Python::attach(|py| {
+            // Structs with flattened fields and fields of internally tagged enums are read as map
+            // entries, so they are only found by their Rust names
+            let obj = py
+                .eval(c"{'userId': 1, 'maxDelay': 5}", None, None)
+                .unwrap();
+            let mut de = Depythonizer::from_object(&obj).with_field_case(FieldCase::CamelCase);
+            let err = Flattened::deserialize(&mut de).unwrap_err();
+            assert_eq!(err.to_string(), "missing field `user_id`");
+
+            let obj = py
+                .eval(c"{'user_id': 1, 'max_delay': 5}", None, None)
+                .unwrap();
+            let mut de = Depythonizer::from_object(&obj).with_field_case(FieldCase::CamelCase);
+            assert_eq!(
+                Flattened::deserialize(&mut de).unwrap(),
+                Flattened {
+                    user_id: 1,
+                    extra: Extra { max_delay: 5 }
+                }
+            );
+
+            let obj = py
+                .eval(c"{'type': 'Backoff', 'maxDelay': 5}", None, None)
+                .unwrap();
+            let mut de = Depythonizer::from_object(&obj).with_field_case(FieldCase::CamelCase);
+            assert!(Tagged::deserialize(&mut de).is_err());
+
+            let obj = py
+                .eval(c"{'type': 'Backoff', 'max_delay': 5}", None, None)
+                .unwrap();
+            let mut de = Depythonizer::from_object(&obj).with_field_case(FieldCase::CamelCase);
+            assert_eq!(
+                Tagged::deserialize(&mut de).unwrap(),
+                Tagged::Backoff { max_delay: 5 }
+            );
+        });
+    }
+
+    #[test]
+    fn test_field_case_deserialize_into() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Request {
+            user_id: u32,
+            retry_policy: Retry,
+        }
+
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Retry {
+            max_delay: u32,
+            attempts: u32,
+        }
+
+        Python::attach(|py| {
+            let mut request = Request {
+                user_id: 1,
+                retry_policy: Retry {
+                    max_delay: 5,
+                    attempts: 2,
+                },
+            };
+
+            let patch = py
+                .eval(c"{'userId': 5, 'retryPolicy': {'maxDelay': 7}}", None, None)
+                .unwrap();
+            Depythonizer::from_object(&patch)
+                .with_field_case(FieldCase::CamelCase)
+                .deserialize_into(&mut request)
+                .unwrap();
+            assert_eq!(request.user_id, 5);
+            assert_eq!(request.retry_policy.max_delay, 7);
+
+            // Fields named as they are replace the same fields
+            let patch = py
+                .eval(
+                    c"{'user_id': 6, 'retry_policy': {'attempts': 3}}",
+                    None,
+                    None,
+                )
+                .unwrap();
+            Depythonizer::from_object(&patch)
+                .with_field_case(FieldCase::CamelCase)
+                .deserialize_into(&mut request)
+                .unwrap();
+            assert_eq!(
+                request,
+                Request {
+                    user_id: 6,
+                    retry_policy: Retry {
+                        max_delay: 7,
+                        attempts: 3
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_depythonize_into() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Config {
+            name: String,
+            tags: Vec<String>,
+            limits: HashMap<String, u32>,
+            retry: Option<Retry>,
+            mode: Mode,
+        }
+
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Retry {
+            attempts: u32,
+            delay: f64,
+        }
+
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        enum Mode {
+            Fast,
+            Careful { checks: u32 },
+            Limited(u32),
+        }
+
+        Python::attach(|py| {
+            let mut config = Config {
+                name: "a".to_string(),
+                tags: vec!["x".to_string(), "y".to_string()],
+                limits: hashmap! {"memory".to_string() => 1, "cpus".to_string() => 2},
+                retry: Some(Retry {
+                    attempts: 3,
+                    delay: 0.5,
+                }),
+                mode: Mode::Careful { checks: 1 },
+            };
+
+            let patch = py
+                .eval(
+                    c"{'tags': ['z'], 'limits': {'cpus': 4, 'disk': 8}, 'retry': {'delay': 1.0}, 'mode': {'Careful': {'checks': 2}}}",
+                    None,
+                    None,
+                )
+                .unwrap();
+            depythonize_into(&patch, &mut config).unwrap();
+            assert_eq!(
+                config,
+                Config {
+                    name: "a".to_string(),
+                    tags: vec!["z".to_string()],
+                    limits: hashmap! {
+                        "memory".to_string() => 1,
+                        "cpus".to_string() => 4,
+                        "disk".to_string() => 8
+                    },
+                    retry: Some(Retry {
+                        attempts: 3,
+                        delay: 1.0,
+                    }),
+                    mode: Mode::Careful { checks: 2 },
+                }
+            );
+
+            // Values which are not mappings are replaced
+            let patch = py
+                .eval(c"{'retry': None, 'mode': 'Fast'}", None, None)
+                .unwrap();
+            depythonize_into(&patch, &mut config).unwrap();
+            assert_eq!(config.retry, None);
+            assert_eq!(config.mode, Mode::Fast);
+
+            // Enums are replaced as a whole, so they can switch between variants with data
+            let patch = py
+                .eval(c"{'mode': {'Careful': {'checks': 3}}}", None, None)
+                .unwrap();
+            depythonize_into(&patch, &mut config).unwrap();
+            assert_eq!(config.mode, Mode::Careful { checks: 3 });
+            let patch = py.eval(c"{'mode': {'Limited': 5}}", None, None).unwrap();
+            depythonize_into(&patch, &mut config).unwrap();
+            assert_eq!(config.mode, Mode::Limited(5));
+
+            // Merged structs must still be complete, and the patch itself is left untouched
+            let patch = py.eval(c"{'retry': {'attempts': 1}}", None, None).unwrap();
+            let err = depythonize_into(&patch, &mut config).unwrap_err();
+            assert_eq!(err.to_string(), "missing field `delay`");
+            assert_eq!(
+                patch.repr().unwrap().to_string(),
+                "{'retry': {'attempts': 1}}"
+            );
+            // `value` is only replaced once the update succeeds
+            assert_eq!(config.retry, None);
+        });
+    }
+
+    #[test]
+    fn test_depythonize_into_skipped_fields() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Session {
+            user: String,
+            #[serde(skip)]
+            cache: Vec<u32>,
+        }
+
+        Python::attach(|py| {
+            let mut session = Session {
+                user: "a".to_string(),
+                cache: vec![1, 2],
+            };
+
+            // Fields which are not serialized are reset to their default by any update
+            let patch = py.eval(c"{}", None, None).unwrap();
+            depythonize_into(&patch, &mut session).unwrap();
+            assert_eq!(
+                session,
+                Session {
+                    user: "a".to_string(),
+                    cache: vec![],
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_depythonize_iter() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Record {
+            name: String,
+            count: u32,
+        }
+
+        Python::attach(|py| {
+            let obj = py
+                .eval(
+                    c"({'name': 'a', 'count': 1}, {'name': 'b'}, {'name': 'c', 'count': 3})",
+                    None,
+                    None,
+                )
+                .unwrap();
+            let mut records = depythonize_iter::<Record>(&obj).unwrap();
+            assert_eq!(records.size_hint(), (3, Some(3)));
+            assert_eq!(
+                records.next().unwrap().unwrap(),
+                Record {
+                    name: "a".to_string(),
+                    count: 1
+                }
+            );
+            let err = records.next().unwrap().unwrap_err();
+            assert_eq!(err.item, Some(1));
+            assert_eq!(err.to_string(), "item 1: missing field `count`");
+            let err = PyErr::from(err);
+            assert_eq!(err.to_string(), "Exception: item 1: missing field `count`");
+            assert_eq!(records.next().unwrap().unwrap().name, "c");
+            assert!(records.next().is_none());
+
+            let obj = py.eval(c"{1}", None, None).unwrap();
+            let items: Vec<i32> = depythonize_iter(&obj)
+                .unwrap()
+                .collect::<Result<_>>()
+                .unwrap();
+            assert_eq!(items, vec![1]);
+
+            let obj = py.eval(c"(str(x) for x in range(2))", None, None).unwrap();
+            assert!(depythonize_iter::<String>(&obj).is_err());
+            let items = Depythonizer::from_object(&obj)
+                .with_iterables(true)
+                .iter::<String>()
+                .unwrap();
+            assert_eq!(items.size_hint(), (0, None));
+            assert_eq!(items.collect::<Result<Vec<_>>>().unwrap(), vec!["0", "1"]);
+
+            // Errors keep the type of the error for the item
+            let obj = py.eval(c"['a', 1]", None, None).unwrap();
+            let err = depythonize_iter::<String>(&obj)
+                .unwrap()
+                .nth(1)
+                .unwrap()
+                .unwrap_err();
+            let err = PyErr::from(err);
+            assert!(err.is_instance_of::<pyo3::exceptions::PyTypeError>(py));
+            assert!(err.to_string().starts_with("TypeError: item 1: "));
+
+            // Exceptions raised in Python are kept, with the index as a note
+            let obj = py.eval(c"(1 // x for x in [1, 0])", None, None).unwrap();
+            let mut items = Depythonizer::from_object(&obj)
+                .with_iterables(true)
+                .iter::<i32>()
+                .unwrap();
+            assert_eq!(items.next().unwrap().unwrap(), 1);
+            let err = items.next().unwrap().unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "item 1: ZeroDivisionError: integer division or modulo by zero"
+            );
+            let err = PyErr::from(err);
+            assert!(err.is_instance_of::<pyo3::exceptions::PyZeroDivisionError>(py));
+            if py.version_info() >= (3, 11) {
+                let notes: Vec<String> = err
+                    .value(py)
+                    .getattr("__notes__")
+                    .unwrap()
+                    .extract()
+                    .unwrap();
+                assert_eq!(notes, vec!["while deserializing item 1"]);
+            }
+        });
+    }
+
+    #[test]
+    fn test_hashmap() {
+        let expected = hashmap! {"foo".to_string() => 4};
+        let expected_json = json!({"foo": 4 });
+        let code = c"{'foo': 4}";
+        test_de(code, &expected, &expected_json);
+    }
+
+    #[test]
+    fn test_map_keys() {
+        #[derive(Debug, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        enum Color {
+            Red,
+            Green,
+        }
+
+        Python::attach(|py| {
+            let obj = py.eval(c"{(1, 2): 'a', (3, 4): 'b'}", None, None).unwrap();
+            let map: HashMap<(i32, i32), String> = depythonize(&obj).unwrap();
+            assert_eq!(map, hashmap! {(1, 2) => "a".into(), (3, 4) => "b".into()});
+
+            let map: HashMap<std::collections::BTreeSet<i32>, i32> =
+                depythonize(&py.eval(c"{frozenset({1, 2}): 1}", None, None).unwrap()).unwrap();
+            assert_eq!(map, hashmap! {[1, 2].into() => 1});
+            let map: HashMap<bool, i32> =
+                depythonize(&py.eval(c"{True: 1, False: 0}", None, None).unwrap()).unwrap();
+            assert_eq!(map, hashmap! {true => 1, false => 0});
+            let map: HashMap<i64, i32> =
+                depythonize(&py.eval(c"{-1: 1, 2 ** 40: 0}", None, None).unwrap()).unwrap();
+            assert_eq!(map, hashmap! {-1 => 1, 1 << 40 => 0});
+
+            // Enum members are read by name, as keys and as values
+            let locals = pyo3::types::PyDict::new(py);
+            py.run(
+                c"import enum\nColor = enum.Enum('Color', ['Red', 'Green'])",
+                None,
+                Some(&locals),
+            )
+            .unwrap();
+            let obj = py
+                .eval(c"{Color.Red: Color.Green}", None, Some(&locals))
+                .unwrap();
+            let map: HashMap<Color, Color> = depythonize(&obj).unwrap();
+            assert_eq!(map, hashmap! {Color::Red => Color::Green});
+            let map: HashMap<String, String> = depythonize(&obj).unwrap();
+            assert_eq!(map, hashmap! {"Red".into() => "Green".into()});
+        });
+    }
+
+    #[test]
+    fn test_stringified_keys() {
+        Python::attach(|py| {
+            let locals = pyo3::types::PyDict::new(py);
+            py.run(c"import enum", None, Some(&locals)).unwrap();
+            let obj = py
+                .eval(
+                    c"{1: 'a', 2.5: 'b', False: 'c', None: 'd', enum.IntEnum('E', {'X': 7}).X: 'e'}",
+                    None,
+                    Some(&locals),
+                )
+                .unwrap();
+            assert!(depythonize::<JsonValue>(&obj).is_err());
+
+            let mut de = Depythonizer::from_object(&obj).with_stringified_keys(true);
+            let value = JsonValue::deserialize(&mut de).unwrap();
+            assert_eq!(
+                value,
+                json!({"1": "a", "2.5": "b", "false": "c", "null": "d", "X": "e"})
+            );
+
+            let mut de = Depythonizer::from_object(&obj).with_stringified_keys(true);
+            let map = HashMap::<String, String>::deserialize(&mut de).unwrap();
+            assert_eq!(map["1"], "a");
+
+            // Only keys are stringified
+            let obj = py.eval(c"{'a': [1]}", None, None).unwrap();
+            let mut de = Depythonizer::from_object(&obj).with_stringified_keys(true);
+            assert!(HashMap::<String, Vec<String>>::deserialize(&mut de).is_err());
+
+            // Tuple keys are rejected, as by `json.dumps`
+            let obj = py.eval(c"{(1, 2): 'a'}", None, None).unwrap();
+            let mut de = Depythonizer::from_object(&obj).with_stringified_keys(true);
+            assert!(JsonValue::deserialize(&mut de).is_err());
+        });
+    }
+
+    #[test]
+    fn test_struct_field_identifiers() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        // Fields are only identified by name, not by their index
+        Python::attach(|py| {
+            let obj = py.eval(c"{'x': 1, 'y': 2}", None, None).unwrap();
+            assert_eq!(depythonize::<Point>(&obj).unwrap(), Point { x: 1, y: 2 });
+
+            let obj = py.eval(c"{0: 1, 'y': 2}", None, None).unwrap();
+            assert!(matches!(
+                *depythonize::<Point>(&obj).unwrap_err().inner,
+                ErrorImpl::DictKeyNotString
+            ));
+
+            let obj = py.eval(c"{(0,): 1, 'y': 2}", None, None).unwrap();
+            assert!(matches!(
+                *depythonize::<Point>(&obj).unwrap_err().inner,
+                ErrorImpl::DictKeyNotString
+            ));
+        });
+    }
 
     #[test]
     fn test_enum_variant() {
@@ -1009,6 +2375,162 @@ mod test {
         })
     }
 
+    #[test]
+    fn test_deserialize_bytes_as_seq() {
+        Python::attach(|py| {
+            let obj = PyBytes::new(py, b"hello");
+            let actual: Vec<u8> = depythonize(&obj).unwrap();
+            assert_eq!(actual, b"hello");
+
+            let actual: [u8; 5] = depythonize(&obj).unwrap();
+            assert_eq!(&actual, b"hello");
+
+            assert!(depythonize::<Vec<i8>>(&PyBytes::new(py, b"\xff")).is_err());
+
+            let data = vec![0u8, 1, 255];
+            let obj = data
+                .serialize(crate::Pythonizer::new(py).with_byte_sequences(true))
+                .unwrap();
+            assert!(obj.is_instance_of::<PyBytes>());
+            assert_eq!(depythonize::<Vec<u8>>(&obj).unwrap(), data);
+        })
+    }
+
+    #[test]
+    fn test_deserialize_bytearray() {
+        Python::attach(|py| {
+            let obj = PyByteArray::new(py, b"hello");
+            let actual: serde_bytes::ByteBuf = depythonize(&obj).unwrap();
+            assert_eq!(actual, b"hello");
+        })
+    }
+
+    #[test]
+    fn test_deserialize_memoryview() {
+        Python::attach(|py| {
+            let obj = py.eval(c"memoryview(b'hello')[1:4]", None, None).unwrap();
+            let actual: serde_bytes::ByteBuf = depythonize(&obj).unwrap();
+            assert_eq!(actual, b"ell");
+
+            // Any object exporting a contiguous buffer
+            let obj = py
+                .eval(c"__import__('array').array('B', [1, 2, 3])", None, None)
+                .unwrap();
+            let actual: serde_bytes::ByteBuf = depythonize(&obj).unwrap();
+            assert_eq!(actual, [1, 2, 3]);
+
+            let obj = py
+                .eval(c"__import__('array').array('b', [1, -1])", None, None)
+                .unwrap();
+            let actual: serde_bytes::ByteBuf = depythonize(&obj).unwrap();
+            assert_eq!(actual, [1, 255]);
+
+            let obj = py.eval(c"memoryview(b'hello')[::2]", None, None).unwrap();
+            let err = depythonize::<serde_bytes::ByteBuf>(&obj).unwrap_err();
+            assert!(matches!(*err.inner, ErrorImpl::UnexpectedType(_)));
+
+            // Only buffers of single bytes
+            for code in [
+                c"__import__('array').array('H', [1, 2])",
+                c"memoryview(b'abcd').cast('I')",
+                c"memoryview(b'abcd').cast('?')",
+            ] {
+                let obj = py.eval(code, None, None).unwrap();
+                let err = depythonize::<serde_bytes::ByteBuf>(&obj).unwrap_err();
+                assert!(matches!(*err.inner, ErrorImpl::UnexpectedType(_)));
+            }
+
+            // Memoryviews of other items are still read as sequences by `deserialize_any`
+            let obj = py
+                .eval(
+                    c"memoryview(__import__('array').array('d', [1.5]))",
+                    None,
+                    None,
+                )
+                .unwrap();
+            assert_eq!(depythonize::<JsonValue>(&obj).unwrap(), json!([1.5]));
+            let obj = py
+                .eval(c"memoryview(bytes([1, 0, 0, 0])).cast('I')", None, None)
+                .unwrap();
+            assert_eq!(depythonize::<JsonValue>(&obj).unwrap(), json!([1]));
+        })
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Borrowing<'a> {
+        #[serde(borrow)]
+        name: Cow<'a, str>,
+    }
+
+    #[test]
+    fn test_borrowed_str_and_bytes() {
+        Python::attach(|py| {
+            let obj = PyBytes::new(py, b"hello").into_any();
+            let actual: &[u8] = depythonize(&obj).unwrap();
+            assert_eq!(actual, b"hello");
+
+            // Tuple items live as long as the tuple
+            let obj = py.eval(c"(b'a', (b'b', 'c'))", None, None).unwrap();
+            let actual: (&[u8], (&[u8], String)) = depythonize(&obj).unwrap();
+            assert_eq!(actual, (&b"a"[..], (&b"b"[..], "c".to_string())));
+
+            let locals = PyDict::new(py);
+            py.run(
+                c"import collections; obj = collections.namedtuple('Borrowing', ['name'])('a')",
+                None,
+                Some(&locals),
+            )
+            .unwrap();
+            let obj = locals.get_item("obj").unwrap().unwrap();
+            let actual: Borrowing<'_> = depythonize(&obj).unwrap();
+            assert_eq!(actual.name, "a");
+        });
+    }
+
+    /// The UTF-8 contents of strings are only available from Python 3.10 with the limited API
+    #[test]
+    #[cfg(any(not(Py_LIMITED_API), Py_3_10))]
+    fn test_borrowed_str() {
+        Python::attach(|py| {
+            let obj = PyString::new(py, "hello").into_any();
+            let actual: &str = depythonize(&obj).unwrap();
+            assert_eq!(actual, "hello");
+
+            let obj = py.eval(c"('a', (b'b', 'c'))", None, None).unwrap();
+            let actual: (&str, (&[u8], &str)) = depythonize(&obj).unwrap();
+            assert_eq!(actual, ("a", (&b"b"[..], "c")));
+
+            let locals = PyDict::new(py);
+            py.run(
+                c"import collections; obj = collections.namedtuple('Borrowing', ['name'])('a')",
+                None,
+                Some(&locals),
+            )
+            .unwrap();
+            let obj = locals.get_item("obj").unwrap().unwrap();
+            let actual: Borrowing<'_> = depythonize(&obj).unwrap();
+            assert!(matches!(actual.name, Cow::Borrowed("a")));
+        });
+    }
+
+    #[test]
+    fn test_borrowed_str_from_mutable_container() {
+        Python::attach(|py| {
+            let obj = py.eval(c"[b'a']", None, None).unwrap();
+            assert!(depythonize::<Vec<&[u8]>>(&obj).is_err());
+
+            let obj = py.eval(c"['a']", None, None).unwrap();
+            assert!(depythonize::<Vec<&str>>(&obj).is_err());
+            let actual: Vec<Cow<'_, str>> = depythonize(&obj).unwrap();
+            assert!(matches!(actual[0], Cow::Owned(_)));
+
+            let obj = py.eval(c"{'name': 'a'}", None, None).unwrap();
+            let actual: Borrowing<'_> = depythonize(&obj).unwrap();
+            assert!(matches!(actual.name, Cow::Owned(_)));
+            assert_eq!(actual.name, "a");
+        });
+    }
+
     #[test]
     fn test_char() {
         let expected = 'a';
@@ -1100,6 +2622,62 @@ point = Point(1, 2)";
         });
     }
 
+    #[test]
+    fn test_dataclass_unset_field() {
+        let code = c"\
+from dataclasses import dataclass, field
+
+@dataclass
+class Point:
+    x: int
+    y: int = field(init=False)
+    label: str = field(init=False)
+
+point = Point(1)
+point.label = 'a'";
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Point {
+            x: i32,
+            y: Option<i32>,
+            label: String,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct DefaultPoint {
+            x: i32,
+            #[serde(default)]
+            y: i32,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct RequiredPoint {
+            x: i32,
+            y: i32,
+        }
+
+        Python::attach(|py| {
+            let locals = PyDict::new(py);
+            // Globals, so that `field` can be used in the class body
+            py.run(code, Some(&locals), None).unwrap();
+            let obj = locals.get_item("point").unwrap().unwrap();
+            assert_eq!(
+                depythonize::<Point>(&obj).unwrap(),
+                Point {
+                    x: 1,
+                    y: None,
+                    label: "a".to_string()
+                }
+            );
+            assert_eq!(
+                depythonize::<DefaultPoint>(&obj).unwrap(),
+                DefaultPoint { x: 1, y: 0 }
+            );
+            let err = depythonize::<RequiredPoint>(&obj).unwrap_err();
+            assert_eq!(err.to_string(), "missing field `y`");
+        });
+    }
+
     #[test]
     fn test_dataclass_extra_field() {
         let code = c"\
@@ -1131,4 +2709,136 @@ point = Point(1, 2, 3)";
             ));
         });
     }
+
+    #[test]
+    fn test_namedtuple() {
+        let code = c"\
+from collections import namedtuple
+
+Point = namedtuple('Point', ['x', 'y'])
+point = Point(1, 2)";
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        Python::attach(|py| {
+            let locals = PyDict::new(py);
+            py.run(code, None, Some(&locals)).unwrap();
+            let obj = locals.get_item("point").unwrap().unwrap();
+            test_de_with_obj(&obj, &Point { x: 1, y: 2 }, &json!([1, 2]));
+
+            let (x, y): (i32, i32) = depythonize(&obj).unwrap();
+            assert_eq!((x, y), (1, 2));
+        });
+    }
+
+    #[test]
+    fn test_as_set_roundtrip() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Tags {
+            #[serde(with = "crate::as_::set")]
+            names: Vec<String>,
+            #[serde(with = "crate::as_::frozenset")]
+            ids: std::collections::BTreeSet<u32>,
+        }
+
+        Python::attach(|py| {
+            let tags = Tags {
+                names: vec!["a".to_string()],
+                ids: [1, 2].into(),
+            };
+            let obj = crate::pythonize(py, &tags).unwrap();
+            assert!(obj.get_item("names").unwrap().is_instance_of::<PySet>());
+            assert!(obj.get_item("ids").unwrap().is_instance_of::<PyFrozenSet>());
+            assert_eq!(depythonize::<Tags>(&obj).unwrap(), tags);
+
+            // Any sequence is accepted
+            let obj = py
+                .eval(c"{'names': ['a'], 'ids': (2, 1)}", None, None)
+                .unwrap();
+            assert_eq!(depythonize::<Tags>(&obj).unwrap(), tags);
+        });
+    }
+
+    #[test]
+    fn test_as_tuple_bytes_decimal_roundtrip() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Reading {
+            #[serde(with = "crate::as_::tuple")]
+            position: Vec<i32>,
+            #[serde(with = "crate::as_::bytes")]
+            raw: Vec<u8>,
+            #[serde(with = "crate::as_::decimal")]
+            price: String,
+            #[serde(with = "crate::as_::decimal")]
+            ratio: f64,
+            #[serde(with = "crate::as_::decimal")]
+            count: i64,
+            #[serde(with = "crate::as_::decimal")]
+            total: u128,
+        }
+
+        Python::attach(|py| {
+            let reading = Reading {
+                position: vec![1, 2],
+                raw: vec![0, 255],
+                price: "1.10".to_string(),
+                ratio: 0.25,
+                count: -3,
+                total: u128::MAX,
+            };
+            let obj = crate::pythonize(py, &reading).unwrap();
+            assert!(obj
+                .get_item("position")
+                .unwrap()
+                .is_instance_of::<PyTuple>());
+            assert!(obj.get_item("raw").unwrap().is_instance_of::<PyBytes>());
+            assert_eq!(depythonize::<Reading>(&obj).unwrap(), reading);
+
+            // Plain Python values are accepted too
+            let obj = py
+                .eval(
+                    c"{'position': [1, 2], 'raw': [0, 255], 'price': '1.10', 'ratio': 0.25, \
+                       'count': -3, 'total': 2**128 - 1}",
+                    None,
+                    None,
+                )
+                .unwrap();
+            assert_eq!(depythonize::<Reading>(&obj).unwrap(), reading);
+
+            // Integers are read from integral decimals only
+            let decimal = py.import("decimal").unwrap().getattr("Decimal").unwrap();
+            obj.set_item("count", decimal.call1(("-3.00",)).unwrap())
+                .unwrap();
+            assert_eq!(depythonize::<Reading>(&obj).unwrap(), reading);
+            obj.set_item("count", decimal.call1(("1.5",)).unwrap())
+                .unwrap();
+            let err = depythonize::<Reading>(&obj).unwrap_err();
+            assert_eq!(err.to_string(), "invalid value: decimal 1.5, expected i64");
+        });
+    }
+
+    #[test]
+    fn test_namedtuple_roundtrip() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Point {
+            x: i32,
+            label: String,
+        }
+
+        Python::attach(|py| {
+            let point = Point {
+                x: 1,
+                label: "origin".to_string(),
+            };
+            let obj = point
+                .serialize(crate::Pythonizer::new(py).with_named_tuples(true))
+                .unwrap();
+            assert!(obj.is_instance_of::<PyTuple>());
+            assert_eq!(depythonize::<Point>(&obj).unwrap(), point);
+        });
+    }
 }
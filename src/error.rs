@@ -1,5 +1,6 @@
-use pyo3::PyErr;
-use pyo3::{exceptions::*, CastError, CastIntoError};
+use pyo3::types::PyAnyMethods;
+use pyo3::{exceptions::*, intern, CastError, CastIntoError};
+use pyo3::{PyErr, Python};
 use serde::{de, ser};
 use std::convert::Infallible;
 use std::error;
@@ -12,6 +13,8 @@ pub type Result<T> = result::Result<T, PythonizeError>;
 /// Errors that can occur when serializing/deserializing Python objects
 pub struct PythonizeError {
     pub(crate) inner: Box<ErrorImpl>,
+    /// The index of the item the error is about, when iterating over a sequence
+    pub(crate) item: Option<usize>,
 }
 
 impl PythonizeError {
@@ -21,6 +24,7 @@ impl PythonizeError {
     {
         Self {
             inner: Box::new(ErrorImpl::Message(text.to_string())),
+            item: None,
         }
     }
 
@@ -30,36 +34,59 @@ impl PythonizeError {
     {
         Self {
             inner: Box::new(ErrorImpl::UnsupportedType(t.to_string())),
+            item: None,
         }
     }
 
     pub(crate) fn dict_key_not_string() -> Self {
         Self {
             inner: Box::new(ErrorImpl::DictKeyNotString),
+            item: None,
         }
     }
 
     pub(crate) fn incorrect_sequence_length(expected: usize, got: usize) -> Self {
         Self {
             inner: Box::new(ErrorImpl::IncorrectSequenceLength { expected, got }),
+            item: None,
         }
     }
 
     pub(crate) fn invalid_enum_type() -> Self {
         Self {
             inner: Box::new(ErrorImpl::InvalidEnumType),
+            item: None,
         }
     }
 
     pub(crate) fn invalid_length_enum() -> Self {
         Self {
             inner: Box::new(ErrorImpl::InvalidLengthEnum),
+            item: None,
         }
     }
 
     pub(crate) fn invalid_length_char() -> Self {
         Self {
             inner: Box::new(ErrorImpl::InvalidLengthChar),
+            item: None,
+        }
+    }
+
+    /// An error for the item at `index` of a sequence. The index is also added as a note to
+    /// exceptions raised in Python (from Python 3.11), which are otherwise kept as they are.
+    pub(crate) fn invalid_item(py: Python<'_>, index: usize, error: PythonizeError) -> Self {
+        if let ErrorImpl::PyErr(err) = error.inner.as_ref() {
+            let note = format!("while deserializing item {}", index);
+            // The note is only informative, so failing to add it is not an error
+            let _ = err
+                .value(py)
+                .getattr(intern!(py, "add_note"))
+                .and_then(|add_note| add_note.call1((note,)));
+        }
+        Self {
+            item: Some(index),
+            ..error
         }
     }
 }
@@ -91,6 +118,9 @@ impl error::Error for PythonizeError {}
 
 impl Display for PythonizeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(index) = self.item {
+            write!(f, "item {}: ", index)?;
+        }
         match self.inner.as_ref() {
             ErrorImpl::PyErr(e) => Display::fmt(e, f),
             ErrorImpl::Message(s) => Display::fmt(s, f),
@@ -122,6 +152,7 @@ impl ser::Error for PythonizeError {
     {
         Self {
             inner: Box::new(ErrorImpl::Message(msg.to_string())),
+            item: None,
         }
     }
 }
@@ -133,6 +164,7 @@ impl de::Error for PythonizeError {
     {
         Self {
             inner: Box::new(ErrorImpl::Message(msg.to_string())),
+            item: None,
         }
     }
 }
@@ -149,6 +181,7 @@ impl From<PyErr> for PythonizeError {
     fn from(other: PyErr) -> Self {
         Self {
             inner: Box::new(ErrorImpl::PyErr(other)),
+            item: None,
         }
     }
 }
@@ -158,6 +191,7 @@ impl<'a, 'py> From<CastError<'a, 'py>> for PythonizeError {
     fn from(other: CastError<'a, 'py>) -> Self {
         Self {
             inner: Box::new(ErrorImpl::UnexpectedType(other.to_string())),
+            item: None,
         }
     }
 }
@@ -167,6 +201,7 @@ impl<'py> From<CastIntoError<'py>> for PythonizeError {
     fn from(other: CastIntoError<'py>) -> Self {
         Self {
             inner: Box::new(ErrorImpl::UnexpectedType(other.to_string())),
+            item: None,
         }
     }
 }
@@ -175,8 +210,9 @@ impl<'py> From<CastIntoError<'py>> for PythonizeError {
 impl From<PythonizeError> for PyErr {
     fn from(other: PythonizeError) -> Self {
         match *other.inner {
+            // Keep exceptions raised in Python, and their traceback, as they are
             ErrorImpl::PyErr(e) => e,
-            ErrorImpl::Message(e) => PyException::new_err(e),
+            ErrorImpl::Message(_) => PyException::new_err(other.to_string()),
             ErrorImpl::UnsupportedType(_)
             | ErrorImpl::UnexpectedType(_)
             | ErrorImpl::DictKeyNotString
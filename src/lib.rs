@@ -1,12 +1,31 @@
 #![doc = include_str!("../README.md")]
 
+pub mod as_;
+mod case;
 mod de;
 mod error;
+mod namedtuple;
+#[cfg(feature = "numpy")]
+mod numpy;
+mod object;
+mod reflect;
+mod schema;
 mod ser;
+mod stub;
+mod value;
 
-pub use crate::de::{depythonize, Depythonizer};
+pub use crate::case::FieldCase;
+pub use crate::de::{
+    depythonize, depythonize_into, depythonize_iter, DepythonizeIter, Depythonizer,
+};
 pub use crate::error::{PythonizeError, Result};
+pub use crate::namedtuple::register_namedtuple;
+pub use crate::object::{PyAnyValue, PyObjectWrapper};
+pub use crate::schema::{json_schema, JsonSchema};
 pub use crate::ser::{
-    pythonize, pythonize_custom, PythonizeDefault, PythonizeListType, PythonizeMappingType,
+    pythonize, pythonize_custom, pythonize_extend, pythonize_into_dict, pythonize_iter,
+    pythonize_onto, PythonizeDefault, PythonizeListType, PythonizeMappingType,
     PythonizeNamedMappingType, PythonizeTypes, PythonizeUnnamedMappingAdapter, Pythonizer,
 };
+pub use crate::stub::typing_stub;
+pub use crate::value::{Int, Value};
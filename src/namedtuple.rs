@@ -0,0 +1,98 @@
+use pyo3::sync::PyOnceLock;
+use pyo3::types::{
+    PyAnyMethods, PyDict, PyDictMethods, PyList, PyListMethods, PyString, PyTuple, PyType,
+};
+use pyo3::{intern, Bound, Py, PyAny, PyResult, Python};
+
+/// Classes registered by the user, as a list of classes for each name
+static REGISTERED: PyOnceLock<Py<PyDict>> = PyOnceLock::new();
+/// Classes created on demand, keyed by `(name, fields)`
+static CREATED: PyOnceLock<Py<PyDict>> = PyOnceLock::new();
+
+fn registry<'a, 'py>(py: Python<'py>, cell: &'a PyOnceLock<Py<PyDict>>) -> &'a Bound<'py, PyDict> {
+    cell.get_or_init(py, || PyDict::new(py).unbind()).bind(py)
+}
+
+/// Register the namedtuple class to use when serializing the Rust struct or tuple struct `name`
+/// with [`Pythonizer::with_named_tuples`](crate::Pythonizer::with_named_tuples).
+///
+/// The class is called with the field values as positional arguments, in declaration order. It
+/// is only used for structs whose field names are the class's `_fields`, or for tuple structs
+/// with as many fields, so that several types with the same name can have their own class.
+pub fn register_namedtuple(py: Python<'_>, name: &str, class: &Bound<'_, PyType>) -> PyResult<()> {
+    let fields = class.getattr(intern!(py, "_fields"))?;
+    let registered = registry(py, &REGISTERED);
+    let classes = match registered.get_item(name)? {
+        Some(classes) => classes.cast_into::<PyList>()?,
+        None => {
+            let classes = PyList::empty(py);
+            registered.set_item(name, &classes)?;
+            classes
+        }
+    };
+    // A class registered later for the same fields replaces the previous one
+    for (i, existing) in classes.iter().enumerate() {
+        if existing.getattr(intern!(py, "_fields"))?.eq(&fields)? {
+            return classes.set_item(i, class);
+        }
+    }
+    classes.append(class)
+}
+
+/// The fields of a struct serialized as a namedtuple
+pub(crate) enum NamedTupleFields<'a> {
+    /// The names of the fields of a struct
+    Named(&'a [&'static str]),
+    /// The number of fields of a tuple struct, which are named `_0`, `_1`, ...
+    Positional(usize),
+}
+
+/// Find the namedtuple class for a struct `name` with the given `fields`, creating it with
+/// `collections.namedtuple` if it has not been registered. Fails for field names which are not
+/// valid in a namedtuple (such as names starting with an underscore).
+pub(crate) fn namedtuple_class<'py>(
+    py: Python<'py>,
+    name: &'static str,
+    fields: NamedTupleFields<'_>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let (names, positional) = match fields {
+        NamedTupleFields::Named(names) => (
+            PyTuple::new(py, names.iter().map(|f| PyString::new(py, f)))?,
+            false,
+        ),
+        NamedTupleFields::Positional(len) => (
+            PyTuple::new(py, (0..len).map(|i| PyString::new(py, &format!("_{}", i))))?,
+            true,
+        ),
+    };
+
+    if let Some(classes) = registry(py, &REGISTERED).get_item(name)? {
+        for class in classes.cast_into::<PyList>()?.iter() {
+            let class_fields = class.getattr(intern!(py, "_fields"))?;
+            let matches = if positional {
+                class_fields.len()? == names.len()?
+            } else {
+                class_fields.eq(&names)?
+            };
+            if matches {
+                return Ok(class);
+            }
+        }
+    }
+
+    let key = (name, &names);
+    let created = registry(py, &CREATED);
+    if let Some(class) = created.get_item(key)? {
+        return Ok(class);
+    }
+
+    // `_0`, `_1`, ... are not valid field names, but are what `rename=True` replaces them with
+    let kwargs = PyDict::new(py);
+    kwargs.set_item(intern!(py, "rename"), positional)?;
+    let class = py
+        .import(intern!(py, "collections"))?
+        .getattr(intern!(py, "namedtuple"))?
+        .call((name, &names), Some(&kwargs))?;
+    created.set_item(key, &class)?;
+    Ok(class)
+}
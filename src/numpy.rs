@@ -0,0 +1,324 @@
+//! Support for NumPy arrays and scalars.
+//!
+//! NumPy is only used through the buffer protocol and its Python API, so this does not depend on
+//! the `numpy` crate and works with any version of NumPy installed at runtime.
+
+#[cfg(any(not(Py_LIMITED_API), Py_3_11))]
+use std::ffi::CStr;
+
+#[cfg(any(not(Py_LIMITED_API), Py_3_11))]
+use pyo3::buffer::{ElementType, PyBuffer, PyUntypedBuffer};
+use pyo3::types::*;
+use pyo3::{intern, Bound, PyAny, PyResult, Python};
+#[cfg(any(not(Py_LIMITED_API), Py_3_11))]
+use serde::de::{self, IntoDeserializer};
+
+#[cfg(any(not(Py_LIMITED_API), Py_3_11))]
+use crate::error::PythonizeError;
+use crate::error::Result;
+
+/// Look up an attribute of the `numpy` module, if it has been imported. Objects created by NumPy
+/// can only exist once it has been imported, so this avoids importing it unnecessarily.
+fn numpy_attr<'py>(
+    py: Python<'py>,
+    attr: &Bound<'py, PyString>,
+) -> PyResult<Option<Bound<'py, PyAny>>> {
+    let modules = py
+        .import(intern!(py, "sys"))?
+        .getattr(intern!(py, "modules"))?;
+    match modules.cast::<PyDict>()?.get_item(intern!(py, "numpy"))? {
+        Some(numpy) => numpy.getattr(attr).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Convert a NumPy scalar or array to the equivalent builtin Python object (with `.item()` and
+/// `.tolist()` respectively), or return `None` if `obj` is neither.
+pub(crate) fn to_builtin<'py>(obj: &Bound<'py, PyAny>) -> Result<Option<Bound<'py, PyAny>>> {
+    let py = obj.py();
+    if let Some(generic) = numpy_attr(py, intern!(py, "generic"))? {
+        if obj.is_instance(&generic)? {
+            return Ok(Some(obj.call_method0(intern!(py, "item"))?));
+        }
+    }
+    if let Some(ndarray) = numpy_attr(py, intern!(py, "ndarray"))? {
+        if obj.is_instance(&ndarray)? {
+            return Ok(Some(obj.call_method0(intern!(py, "tolist"))?));
+        }
+    }
+    Ok(None)
+}
+
+/// Convert the serialized elements of a sequence to a NumPy array, if they are all numbers or
+/// all booleans. Empty sequences are left as they are, as their element type is unknown.
+pub(crate) fn to_ndarray<'py>(
+    py: Python<'py>,
+    items: &[Bound<'py, PyAny>],
+) -> Result<Option<Bound<'py, PyAny>>> {
+    let is_bool = |item: &Bound<'py, PyAny>| item.is_instance_of::<PyBool>();
+    let is_number = |item: &Bound<'py, PyAny>| {
+        (item.is_instance_of::<PyInt>() && !is_bool(item)) || item.is_instance_of::<PyFloat>()
+    };
+    if items.is_empty() || !(items.iter().all(is_number) || items.iter().all(is_bool)) {
+        return Ok(None);
+    }
+    let array = py
+        .import(intern!(py, "numpy"))?
+        .getattr(intern!(py, "array"))?
+        .call1((PyList::new(py, items)?,))?;
+    Ok(Some(array))
+}
+
+/// The elements of a buffer of one of the supported numeric types
+#[cfg(any(not(Py_LIMITED_API), Py_3_11))]
+enum Elements {
+    I8(PyBuffer<i8>),
+    I16(PyBuffer<i16>),
+    I32(PyBuffer<i32>),
+    I64(PyBuffer<i64>),
+    U8(PyBuffer<u8>),
+    U16(PyBuffer<u16>),
+    U32(PyBuffer<u32>),
+    U64(PyBuffer<u64>),
+    F32(PyBuffer<f32>),
+    F64(PyBuffer<f64>),
+    /// Booleans, read as bytes
+    Bool(PyBuffer<u8>),
+}
+
+/// Sequence access over the elements of a one-dimensional, contiguous buffer of numbers, such as
+/// a NumPy array or an `array.array`, which reads them directly from the buffer rather than one
+/// object at a time.
+#[cfg(any(not(Py_LIMITED_API), Py_3_11))]
+pub(crate) struct PyNumericBufferAccess<'py> {
+    py: Python<'py>,
+    elements: Elements,
+    len: usize,
+    index: usize,
+}
+
+#[cfg(any(not(Py_LIMITED_API), Py_3_11))]
+impl<'py> PyNumericBufferAccess<'py> {
+    pub(crate) fn new(obj: &Bound<'py, PyAny>) -> Result<Option<Self>> {
+        // SAFETY: `obj` is a valid object, and this only looks up its type's buffer slot
+        if unsafe { pyo3::ffi::PyObject_CheckBuffer(obj.as_ptr()) } == 0 {
+            return Ok(None);
+        }
+        let Ok(buffer) = PyUntypedBuffer::get(obj) else {
+            return Ok(None);
+        };
+        if buffer.dimensions() != 1 || !buffer.is_c_contiguous() || !is_native(buffer.format()) {
+            return Ok(None);
+        }
+
+        let len = buffer.item_count();
+        let elements = match ElementType::from_format(buffer.format()) {
+            ElementType::SignedInteger { bytes: 1 } => buffer.into_typed().map(Elements::I8),
+            ElementType::SignedInteger { bytes: 2 } => buffer.into_typed().map(Elements::I16),
+            ElementType::SignedInteger { bytes: 4 } => buffer.into_typed().map(Elements::I32),
+            ElementType::SignedInteger { bytes: 8 } => buffer.into_typed().map(Elements::I64),
+            ElementType::UnsignedInteger { bytes: 1 } => buffer.into_typed().map(Elements::U8),
+            ElementType::UnsignedInteger { bytes: 2 } => buffer.into_typed().map(Elements::U16),
+            ElementType::UnsignedInteger { bytes: 4 } => buffer.into_typed().map(Elements::U32),
+            ElementType::UnsignedInteger { bytes: 8 } => buffer.into_typed().map(Elements::U64),
+            ElementType::Float { bytes: 4 } => buffer.into_typed().map(Elements::F32),
+            ElementType::Float { bytes: 8 } => buffer.into_typed().map(Elements::F64),
+            ElementType::Bool if buffer.item_size() == 1 => {
+                let py = obj.py();
+                let bytes = PyMemoryView::from(obj)?
+                    .call_method1(intern!(py, "cast"), (intern!(py, "B"),))?;
+                PyBuffer::get(&bytes).map(Elements::Bool)
+            }
+            _ => return Ok(None),
+        };
+        // Misaligned buffers are read item by item instead
+        Ok(elements.ok().map(|elements| Self {
+            py: obj.py(),
+            elements,
+            len,
+            index: 0,
+        }))
+    }
+}
+
+/// Whether a `struct` module format string is in native byte order
+#[cfg(any(not(Py_LIMITED_API), Py_3_11))]
+fn is_native(format: &CStr) -> bool {
+    let native = if cfg!(target_endian = "little") {
+        b'<'
+    } else {
+        b'>'
+    };
+    match format.to_bytes() {
+        [prefix, _] => [b'@', b'=', native].contains(prefix),
+        _ => true,
+    }
+}
+
+#[cfg(any(not(Py_LIMITED_API), Py_3_11))]
+impl<'de> de::SeqAccess<'de> for PyNumericBufferAccess<'_> {
+    type Error = PythonizeError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        if self.index == self.len {
+            return Ok(None);
+        }
+        let index = self.index;
+        self.index += 1;
+
+        // The buffer is C-contiguous, so it can always be read as a slice
+        macro_rules! element {
+            ($buffer:expr) => {
+                $buffer.as_slice(self.py).expect("contiguous buffer")[index].get()
+            };
+        }
+        match &self.elements {
+            Elements::I8(buffer) => seed.deserialize(element!(buffer).into_deserializer()),
+            Elements::I16(buffer) => seed.deserialize(element!(buffer).into_deserializer()),
+            Elements::I32(buffer) => seed.deserialize(element!(buffer).into_deserializer()),
+            Elements::I64(buffer) => seed.deserialize(element!(buffer).into_deserializer()),
+            Elements::U8(buffer) => seed.deserialize(element!(buffer).into_deserializer()),
+            Elements::U16(buffer) => seed.deserialize(element!(buffer).into_deserializer()),
+            Elements::U32(buffer) => seed.deserialize(element!(buffer).into_deserializer()),
+            Elements::U64(buffer) => seed.deserialize(element!(buffer).into_deserializer()),
+            Elements::F32(buffer) => seed.deserialize(element!(buffer).into_deserializer()),
+            Elements::F64(buffer) => seed.deserialize(element!(buffer).into_deserializer()),
+            Elements::Bool(buffer) => seed.deserialize((element!(buffer) != 0).into_deserializer()),
+        }
+        .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len - self.index)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{depythonize, Pythonizer};
+    use pyo3::prelude::*;
+    use pyo3::types::PyList;
+    use serde::Serialize;
+
+    /// Run `f` with the `numpy` module. Tests using it are ignored by default, and run with
+    /// `--include-ignored` where numpy is installed.
+    fn with_numpy(f: impl FnOnce(&Bound<'_, PyModule>)) {
+        Python::attach(|py| f(&py.import("numpy").expect("numpy is not installed")))
+    }
+
+    #[test]
+    fn test_buffer_to_vec() {
+        Python::attach(|py| {
+            let array = py.import("array").unwrap().getattr("array").unwrap();
+
+            let obj = array.call1(("d", vec![1.5, -2.0])).unwrap();
+            assert_eq!(depythonize::<Vec<f64>>(&obj).unwrap(), vec![1.5, -2.0]);
+            assert_eq!(depythonize::<Vec<f32>>(&obj).unwrap(), vec![1.5, -2.0]);
+
+            let obj = array.call1(("i", vec![1, -2, 3])).unwrap();
+            assert_eq!(depythonize::<Vec<i64>>(&obj).unwrap(), vec![1, -2, 3]);
+            assert!(depythonize::<Vec<u32>>(&obj).is_err());
+
+            let obj = array.call1(("Q", vec![u64::MAX])).unwrap();
+            assert_eq!(depythonize::<Vec<u64>>(&obj).unwrap(), vec![u64::MAX]);
+
+            let obj = array.call1(("b", vec![-1, 2])).unwrap();
+            assert_eq!(depythonize::<Vec<i8>>(&obj).unwrap(), vec![-1, 2]);
+
+            let obj = py
+                .eval(c"memoryview(bytes([1, 0])).cast('?')", None, None)
+                .unwrap();
+            assert_eq!(depythonize::<Vec<bool>>(&obj).unwrap(), vec![true, false]);
+
+            // Lists are still read item by item
+            let obj = PyList::new(py, [1, 2]).unwrap();
+            assert_eq!(depythonize::<Vec<u8>>(&obj).unwrap(), vec![1, 2]);
+        });
+    }
+
+    #[test]
+    #[ignore = "requires numpy"]
+    fn test_ndarray_to_vec() {
+        with_numpy(|numpy| {
+            let obj = numpy.call_method1("arange", (4.0,)).unwrap();
+            assert_eq!(
+                depythonize::<Vec<f64>>(&obj).unwrap(),
+                vec![0.0, 1.0, 2.0, 3.0]
+            );
+
+            // Non-contiguous and multi-dimensional arrays
+            let obj = obj
+                .get_item(pyo3::types::PySlice::new(obj.py(), 0, 4, 2))
+                .unwrap();
+            assert_eq!(depythonize::<Vec<f64>>(&obj).unwrap(), vec![0.0, 2.0]);
+            let obj = numpy
+                .call_method1("arange", (4,))
+                .unwrap()
+                .call_method1("reshape", (2, 2))
+                .unwrap();
+            assert_eq!(
+                depythonize::<Vec<Vec<i32>>>(&obj).unwrap(),
+                vec![vec![0, 1], vec![2, 3]]
+            );
+            assert_eq!(
+                depythonize::<serde_json::Value>(&obj).unwrap(),
+                serde_json::json!([[0, 1], [2, 3]])
+            );
+
+            // Non-native byte order
+            let obj = numpy.call_method1("array", ([1, 256], ">i4")).unwrap();
+            assert_eq!(depythonize::<Vec<i32>>(&obj).unwrap(), vec![1, 256]);
+            assert_eq!(
+                depythonize::<serde_json::Value>(&obj).unwrap(),
+                serde_json::json!([1, 256])
+            );
+        });
+    }
+
+    #[test]
+    #[ignore = "requires numpy"]
+    fn test_numpy_scalars() {
+        with_numpy(|numpy| {
+            let obj = numpy.call_method1("int32", (7,)).unwrap();
+            assert_eq!(
+                depythonize::<serde_json::Value>(&obj).unwrap(),
+                serde_json::json!(7)
+            );
+            assert_eq!(depythonize::<u8>(&obj).unwrap(), 7);
+
+            let obj = numpy.call_method1("bool_", (true,)).unwrap();
+            assert_eq!(
+                depythonize::<serde_json::Value>(&obj).unwrap(),
+                serde_json::json!(true)
+            );
+        });
+    }
+
+    #[test]
+    fn test_serialize_numpy_arrays() {
+        Python::attach(|py| {
+            // Non-numeric sequences don't need numpy
+            let obj = vec!["a", "b"]
+                .serialize(Pythonizer::new(py).with_numpy_arrays(true))
+                .unwrap();
+            assert!(obj.is_instance_of::<PyList>());
+        });
+    }
+
+    #[test]
+    #[ignore = "requires numpy"]
+    fn test_serialize_ndarrays() {
+        with_numpy(|numpy| {
+            let ndarray = numpy.getattr("ndarray").unwrap();
+            let obj = vec![vec![1.0, 2.0], vec![]]
+                .serialize(Pythonizer::new(numpy.py()).with_numpy_arrays(true))
+                .unwrap();
+            assert!(obj.is_instance_of::<PyList>());
+            assert!(obj.get_item(0).unwrap().is_instance(&ndarray).unwrap());
+            assert!(obj.get_item(1).unwrap().is_instance_of::<PyList>());
+        });
+    }
+}
@@ -0,0 +1,263 @@
+use std::cell::Cell;
+use std::fmt;
+
+use pyo3::{Bound, Py, PyAny, Python};
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+pub(crate) const OBJECT_TOKEN: &str = "$pythonize::private::Object";
+
+thread_local! {
+    /// The object being passed from a wrapper to `Pythonizer`, which can't be expressed in
+    /// serde's data model
+    static SERIALIZED: Cell<Option<Py<PyAny>>> = const { Cell::new(None) };
+    /// The object being passed from `Depythonizer` to a wrapper's visitor
+    static DESERIALIZED: Cell<Option<Py<PyAny>>> = const { Cell::new(None) };
+}
+
+pub(crate) fn take_serialized() -> Option<Py<PyAny>> {
+    SERIALIZED.with(Cell::take)
+}
+
+/// Make `obj` available to the visitors of the wrappers while `f` runs. The object is never left
+/// behind, so it can only be taken by a visitor called by `Depythonizer`.
+pub(crate) fn with_deserialized<R>(obj: Py<PyAny>, f: impl FnOnce() -> R) -> R {
+    DESERIALIZED.with(|cell| cell.set(Some(obj)));
+    let result = f();
+    DESERIALIZED.with(Cell::take);
+    result
+}
+
+/// Make `obj` available to `Pythonizer` while `serializer` serializes it. As for
+/// [`with_deserialized`], the object is never left behind, even if a serializer rejects the
+/// newtype struct without serializing its contents.
+fn serialize_object<S>(obj: Py<PyAny>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    SERIALIZED.with(|cell| cell.set(Some(obj)));
+    let result = serializer.serialize_newtype_struct(OBJECT_TOKEN, &Unsupported);
+    SERIALIZED.with(Cell::take);
+    result
+}
+
+/// A Python object embedded in a Rust value, which [`pythonize`](crate::pythonize) inserts as it
+/// is (by reference) and [`depythonize`](crate::depythonize) captures without converting it.
+///
+/// ```
+/// use pyo3::prelude::*;
+/// use pythonize::{depythonize, pythonize, PyObjectWrapper};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Task {
+///     name: String,
+///     callback: PyObjectWrapper,
+/// }
+///
+/// Python::attach(|py| {
+///     let callback = py.eval(c"print", None, None).unwrap();
+///     let task = Task { name: "log".into(), callback: PyObjectWrapper(callback.clone().unbind()) };
+///
+///     let obj = pythonize(py, &task).unwrap();
+///     assert!(obj.get_item("callback").unwrap().is(&callback));
+///
+///     let task: Task = depythonize(&obj).unwrap();
+///     assert!(task.callback.0.is(&callback));
+/// })
+/// ```
+///
+/// Other serializers and deserializers fail with an error when they encounter this type.
+#[derive(Debug)]
+pub struct PyObjectWrapper(pub Py<PyAny>);
+
+impl From<Py<PyAny>> for PyObjectWrapper {
+    fn from(obj: Py<PyAny>) -> Self {
+        PyObjectWrapper(obj)
+    }
+}
+
+impl Serialize for PyObjectWrapper {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        // `Py::clone_ref` needs to be attached to the interpreter, which `Pythonizer` is
+        serialize_object(Python::attach(|py| self.0.clone_ref(py)), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PyObjectWrapper {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_newtype_struct(OBJECT_TOKEN, ObjectVisitor)
+            .map(PyObjectWrapper)
+    }
+}
+
+/// A Python object captured by [`depythonize`](crate::depythonize) without converting it, e.g. for
+/// fields holding callbacks or model instances. The object can only be borrowed for as long as
+/// the input of `depythonize`.
+///
+/// ```
+/// use pyo3::prelude::*;
+/// use pythonize::{depythonize, PyAnyValue};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Task<'py> {
+///     name: String,
+///     #[serde(borrow)]
+///     callback: PyAnyValue<'py>,
+/// }
+///
+/// Python::attach(|py| {
+///     let obj = py.eval(c"{'name': 'log', 'callback': len}", None, None).unwrap();
+///     let task: Task = depythonize(&obj).unwrap();
+///     let len = task.callback.0.call1((&task.name,)).unwrap();
+///     assert_eq!(len.extract::<usize>().unwrap(), 3);
+/// })
+/// ```
+///
+/// It is serialized by [`pythonize`](crate::pythonize) as the object itself, like
+/// [`PyObjectWrapper`]. Other serializers and deserializers fail with an error when they
+/// encounter this type.
+#[derive(Debug, Clone)]
+pub struct PyAnyValue<'py>(pub Bound<'py, PyAny>);
+
+impl<'py> From<Bound<'py, PyAny>> for PyAnyValue<'py> {
+    fn from(obj: Bound<'py, PyAny>) -> Self {
+        PyAnyValue(obj)
+    }
+}
+
+impl Serialize for PyAnyValue<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_object(self.0.clone().unbind(), serializer)
+    }
+}
+
+impl<'de: 'py, 'py> Deserialize<'de> for PyAnyValue<'py> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let obj = deserializer.deserialize_newtype_struct(OBJECT_TOKEN, ObjectVisitor)?;
+        // SAFETY: objects are only handed to `ObjectVisitor` by a `Depythonizer<'de, 'py>`, which
+        // requires the thread to be attached to the interpreter for a lifetime which outlives `'de`.
+        let py = unsafe { Python::assume_attached() };
+        Ok(PyAnyValue(obj.into_bound(py)))
+    }
+}
+
+/// Serialized in place of the object by serializers other than `Pythonizer`
+struct Unsupported;
+
+impl Serialize for Unsupported {
+    fn serialize<S>(&self, _serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Err(ser::Error::custom(
+            "Python objects can only be serialized by pythonize",
+        ))
+    }
+}
+
+struct ObjectVisitor;
+
+impl<'de> de::Visitor<'de> for ObjectVisitor {
+    type Value = Py<PyAny>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a Python object")
+    }
+
+    fn visit_newtype_struct<D>(self, _deserializer: D) -> Result<Py<PyAny>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        DESERIALIZED.with(Cell::take).ok_or_else(|| {
+            de::Error::custom("Python objects can only be deserialized by pythonize")
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PyAnyValue, PyObjectWrapper};
+    use crate::{depythonize, pythonize};
+    use pyo3::prelude::*;
+    use pyo3::types::PyDict;
+    use serde::{Deserialize, Serialize};
+
+    #[test]
+    fn test_object_wrapper_roundtrip() {
+        Python::attach(|py| {
+            let dict = PyDict::new(py);
+            let objects = vec![
+                Some(PyObjectWrapper(dict.clone().into_any().unbind())),
+                None,
+            ];
+
+            let obj = pythonize(py, &objects).unwrap();
+            assert!(obj.get_item(0).unwrap().is(&dict));
+            assert!(obj.get_item(1).unwrap().is_none());
+
+            let objects: Vec<Option<PyObjectWrapper>> = depythonize(&obj).unwrap();
+            assert!(objects[0].as_ref().unwrap().0.is(&dict));
+            assert!(objects[1].is_none());
+        });
+    }
+
+    #[test]
+    fn test_any_value() {
+        #[derive(Deserialize, Serialize)]
+        struct Model<'py> {
+            #[serde(borrow)]
+            instance: PyAnyValue<'py>,
+            #[serde(borrow)]
+            extra: Vec<PyAnyValue<'py>>,
+        }
+
+        Python::attach(|py| {
+            let obj = py
+                .eval(c"{'instance': object(), 'extra': [[1], None]}", None, None)
+                .unwrap();
+            let instance = obj.get_item("instance").unwrap();
+            let model: Model = depythonize(&obj).unwrap();
+            assert!(model.instance.0.is(&instance));
+            assert_eq!(model.extra[0].0.repr().unwrap().to_string(), "[1]");
+            assert!(model.extra[1].0.is_none());
+
+            let roundtrip = pythonize(py, &model).unwrap();
+            assert!(roundtrip.get_item("instance").unwrap().is(&instance));
+
+            assert!(serde_json::from_str::<Vec<PyAnyValue>>("[1]").is_err());
+            assert!(serde_json::to_string(&model).is_err());
+        });
+    }
+
+    #[test]
+    fn test_object_wrapper_other_formats() {
+        Python::attach(|py| {
+            let wrapper = PyObjectWrapper(py.None());
+            let err = serde_json::to_string(&wrapper).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Python objects can only be serialized by pythonize"
+            );
+            assert!(super::take_serialized().is_none());
+
+            let err = serde_json::from_str::<PyObjectWrapper>("1").unwrap_err();
+            assert!(err
+                .to_string()
+                .starts_with("Python objects can only be deserialized by pythonize"));
+        });
+    }
+}
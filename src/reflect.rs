@@ -0,0 +1,864 @@
+//! Discovery of the Python-side shape of Rust types, by tracing their `Deserialize`
+//! implementations with a deserializer that records what it is asked for.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Display};
+
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::Deserialize;
+
+use crate::as_;
+use crate::error::{PythonizeError, Result};
+
+/// The shape of a value exchanged with Python
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Format {
+    /// Any Python object, e.g. for types which use `deserialize_any`
+    Any,
+    Unit,
+    Bool,
+    Int,
+    Float,
+    Str,
+    Bytes,
+    /// A value serialized with [`as_::decimal`](crate::as_::decimal)
+    Decimal,
+    Option(Box<Format>),
+    Seq(Box<Format>),
+    /// A sequence serialized with [`as_::tuple`](crate::as_::tuple)
+    VarTuple(Box<Format>),
+    /// A sequence serialized with [`as_::set`](crate::as_::set)
+    Set(Box<Format>),
+    /// A sequence serialized with [`as_::frozenset`](crate::as_::frozenset)
+    FrozenSet(Box<Format>),
+    Tuple(Vec<Format>),
+    Map(Box<Format>, Box<Format>),
+    /// A struct or enum, described by a [`Container`] in the [`Registry`]
+    Named(&'static str),
+}
+
+/// A struct or enum which is referred to by name
+#[derive(Debug)]
+pub(crate) enum Container {
+    Struct(Vec<Field>),
+    /// Variants which have not been traced yet are `None`
+    Enum(Vec<(&'static str, Option<VariantFormat>)>),
+}
+
+#[derive(Debug)]
+pub(crate) enum VariantFormat {
+    Unit,
+    NewType(Format),
+    Tuple(Vec<Format>),
+    Struct(Vec<Field>),
+}
+
+/// A field of a struct or struct variant
+#[derive(Debug)]
+pub(crate) struct Field {
+    pub(crate) name: &'static str,
+    pub(crate) format: Format,
+    /// Whether the field must be present when deserializing, i.e. it has no `#[serde(default)]`
+    /// and is not an `Option`
+    pub(crate) required: bool,
+}
+
+fn fields(names: &'static [&'static str], formats: Vec<Format>) -> Vec<Field> {
+    names
+        .iter()
+        .zip(formats)
+        .map(|(name, format)| Field {
+            name,
+            required: !matches!(format, Format::Option(_)),
+            format,
+        })
+        .collect()
+}
+
+/// A struct (by name) or struct variant (by enum and variant name)
+type StructKey = (&'static str, Option<&'static str>);
+
+/// All structs and enums discovered while tracing, in the order they were first seen
+#[derive(Debug, Default)]
+pub(crate) struct Registry {
+    containers: BTreeMap<&'static str, Container>,
+    order: Vec<&'static str>,
+}
+
+impl Registry {
+    /// Iterate over the containers in the order they were discovered
+    pub(crate) fn containers(&self) -> impl Iterator<Item = (&'static str, &Container)> {
+        self.order
+            .iter()
+            .map(|name| (*name, &self.containers[name]))
+    }
+
+    fn insert(&mut self, name: &'static str, container: Container) {
+        if self.containers.insert(name, container).is_none() {
+            self.order.push(name);
+        }
+    }
+
+    fn fields_mut(&mut self, key: StructKey) -> Option<&mut Vec<Field>> {
+        match (self.containers.get_mut(key.0)?, key.1) {
+            (Container::Struct(fields), None) => Some(fields),
+            (Container::Enum(variants), Some(variant)) => {
+                match variants.iter_mut().find(|(v, _)| *v == variant)? {
+                    (_, Some(VariantFormat::Struct(fields))) => Some(fields),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// All fields of structs and struct variants
+    fn struct_fields(&self) -> Vec<(StructKey, &'static str)> {
+        let mut all = Vec::new();
+        for (name, container) in self.containers() {
+            match container {
+                Container::Struct(fields) => {
+                    all.extend(fields.iter().map(|field| ((name, None), field.name)));
+                }
+                Container::Enum(variants) => {
+                    for (variant, format) in variants {
+                        if let Some(VariantFormat::Struct(fields)) = format {
+                            all.extend(
+                                fields
+                                    .iter()
+                                    .map(|field| ((name, Some(*variant)), field.name)),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        all
+    }
+
+    fn is_complete(&self) -> bool {
+        self.containers.values().all(|container| match container {
+            Container::Struct(_) => true,
+            Container::Enum(variants) => variants.iter().all(|(_, v)| v.is_some()),
+        })
+    }
+}
+
+/// Trace the shape of `T` and record the containers it uses in `registry`.
+///
+/// The type is deserialized repeatedly until every variant of every enum it contains has been
+/// visited. Recursive types are supported when the recursion goes through an `Option`, sequence
+/// or map, which are traced as empty once their element is known.
+///
+/// Each field of each struct is then left out once, to find out whether the `Deserialize`
+/// implementation accepts its absence (e.g. because of `#[serde(default)]`).
+pub(crate) fn trace<'de, T>(registry: &mut Registry) -> Result<Format>
+where
+    T: Deserialize<'de>,
+{
+    let mut tracer = Tracer {
+        registry,
+        stack: Vec::new(),
+        path: Vec::new(),
+        sites: HashMap::new(),
+        deferred: HashSet::new(),
+        terminal: HashSet::new(),
+        progress: 0,
+        choices: Vec::new(),
+        routes: HashMap::new(),
+        probe: None,
+    };
+    let format = loop {
+        let progress = tracer.progress;
+        let mut format = Format::Any;
+        let result = T::deserialize(TraceDeserializer {
+            tracer: &mut tracer,
+            format: &mut format,
+        })
+        .map_err(|err| tracer.any_error(err, &format));
+        tracer.stack.clear();
+        tracer.path.clear();
+        tracer.choices.clear();
+        match result {
+            Ok(_) if tracer.registry.is_complete() => break format,
+            Ok(_) | Err(TraceError::Recursion(_)) if tracer.progress > progress => continue,
+            Ok(_) => return Err(PythonizeError::msg("unable to trace all enum variants")),
+            Err(TraceError::Recursion(name)) => {
+                return Err(PythonizeError::msg(format!(
+                    "unable to trace recursive type `{}`",
+                    name
+                )))
+            }
+            Err(err) => return Err(PythonizeError::msg(err)),
+        }
+    };
+
+    let mut required = Vec::new();
+    for (key, field) in tracer.registry.struct_fields() {
+        let Some(route) = tracer.routes.get(&key).cloned() else {
+            continue;
+        };
+        tracer.probe = Some(Probe {
+            key,
+            field,
+            route,
+            required: None,
+        });
+        let _ = T::deserialize(TraceDeserializer {
+            tracer: &mut tracer,
+            format: &mut Format::Any,
+        });
+        tracer.stack.clear();
+        tracer.path.clear();
+        tracer.choices.clear();
+        if let Some(Probe {
+            required: Some(is_required),
+            ..
+        }) = tracer.probe.take()
+        {
+            required.push((key, field, is_required));
+        }
+    }
+    // Containers are traced again by each probe, so the results are only recorded at the end
+    for (key, field, is_required) in required {
+        if let Some(fields) = tracer.registry.fields_mut(key) {
+            for f in fields.iter_mut().filter(|f| f.name == field) {
+                f.required = is_required;
+            }
+        }
+    }
+    Ok(format)
+}
+
+#[derive(Debug)]
+enum TraceError {
+    /// A struct or enum was reached again while it was still being traced
+    Recursion(&'static str),
+    Message(String),
+    /// A value deserialized with `deserialize_any` rejected the unit it was given
+    Any(String),
+    /// A struct field was missing, which only happens when it is left out by a [`Probe`]
+    MissingField(&'static str),
+}
+
+impl Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceError::Recursion(name) => write!(f, "recursive type `{}`", name),
+            TraceError::Message(msg) | TraceError::Any(msg) => f.write_str(msg),
+            TraceError::MissingField(field) => write!(f, "missing field `{}`", field),
+        }
+    }
+}
+
+impl std::error::Error for TraceError {}
+
+impl de::Error for TraceError {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        TraceError::Message(msg.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        TraceError::MissingField(field)
+    }
+}
+
+type TraceResult<T> = std::result::Result<T, TraceError>;
+
+struct Tracer<'r> {
+    registry: &'r mut Registry,
+    /// Containers currently being traced
+    stack: Vec<&'static str>,
+    /// Location of the value currently being traced
+    path: Vec<String>,
+    /// Options, sequences and maps (by path) whose element recurses into a container
+    sites: HashMap<String, Format>,
+    /// Enum variants (by index) which recursed, to be retried once more variants are known
+    deferred: HashSet<(&'static str, usize)>,
+    /// Enum variants (by index) for which a value could be produced
+    terminal: HashSet<(&'static str, usize)>,
+    /// Incremented whenever something new is learnt, to detect when tracing is stuck
+    progress: usize,
+    /// Enum variants (by index) chosen on the way to the value currently being traced
+    choices: Vec<(&'static str, usize)>,
+    /// The variants chosen on the way to each struct the first time it was traced
+    routes: HashMap<StructKey, Vec<(&'static str, usize)>>,
+    /// The field left out while checking whether it is required
+    probe: Option<Probe>,
+}
+
+/// Leave out `field` whenever the struct `key` is traced, to see whether it is required
+struct Probe {
+    key: StructKey,
+    field: &'static str,
+    /// The enum variants to choose to reach the struct
+    route: Vec<(&'static str, usize)>,
+    /// `None` until the struct was traced without the field
+    required: Option<bool>,
+}
+
+impl Tracer<'_> {
+    fn trace_child<'de, S>(
+        &mut self,
+        segment: impl Into<String>,
+        seed: S,
+        format: &mut Format,
+    ) -> TraceResult<S::Value>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        self.path.push(segment.into());
+        let result = seed
+            .deserialize(TraceDeserializer {
+                tracer: self,
+                format: &mut *format,
+            })
+            .map_err(|err| self.any_error(err, format));
+        self.path.pop();
+        result
+    }
+
+    /// Explain the failure of a value which was only asked for with `deserialize_any`.
+    ///
+    /// Such values are traced by visiting a unit, which is enough for self-describing types
+    /// such as `serde_json::Value`, but is rejected by untagged and internally tagged enums.
+    fn any_error(&self, err: TraceError, format: &Format) -> TraceError {
+        match err {
+            TraceError::Message(msg) if *format == Format::Any => {
+                let location = if self.path.is_empty() {
+                    "the root".to_string()
+                } else {
+                    format!("`{}`", self.path.join("."))
+                };
+                TraceError::Any(format!(
+                    "unable to trace {}, which can only be deserialized with \
+                     `deserialize_any` (e.g. an untagged or internally tagged enum): {}",
+                    location, msg
+                ))
+            }
+            err => err,
+        }
+    }
+
+    /// Remember that the element of the option, sequence or map at `site` recurses, so that it
+    /// is traced as empty from now on.
+    fn record_site<T>(
+        &mut self,
+        site: String,
+        result: &TraceResult<T>,
+        element: &Format,
+        format: &Format,
+    ) {
+        if let (Err(TraceError::Recursion(_)), Format::Named(_)) = (result, element) {
+            self.sites.insert(site, format.clone());
+            self.progress += 1;
+        }
+    }
+
+    /// The field to leave out of the struct `key`, if it is being probed
+    fn probed_field(&self, key: StructKey) -> Option<&'static str> {
+        self.probe
+            .as_ref()
+            .filter(|probe| probe.key == key)
+            .map(|probe| probe.field)
+    }
+
+    /// Record whether a struct being probed could be deserialized without its field, and the
+    /// route to a struct traced for the first time
+    fn traced_struct<T>(&mut self, key: StructKey, result: &TraceResult<T>) {
+        if let Some(probe) = self.probe.as_mut().filter(|probe| probe.key == key) {
+            match result {
+                Ok(_) => probe.required = Some(false),
+                Err(TraceError::MissingField(field)) if *field == probe.field => {
+                    probe.required = Some(true)
+                }
+                Err(_) => {}
+            }
+        } else if result.is_ok() && !self.routes.contains_key(&key) {
+            self.routes.insert(key, self.choices.clone());
+        }
+    }
+}
+
+struct TraceDeserializer<'t, 'r> {
+    tracer: &'t mut Tracer<'r>,
+    format: &'t mut Format,
+}
+
+macro_rules! trace_primitive {
+    ($method:ident => $format:ident, $visit:ident($($value:expr)?)) => {
+        fn $method<V>(self, visitor: V) -> TraceResult<V::Value>
+        where
+            V: Visitor<'de>,
+        {
+            *self.format = Format::$format;
+            visitor.$visit($($value)?)
+        }
+    };
+}
+
+impl<'de> de::Deserializer<'de> for TraceDeserializer<'_, '_> {
+    type Error = TraceError;
+
+    fn deserialize_any<V>(self, visitor: V) -> TraceResult<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        *self.format = Format::Any;
+        visitor.visit_unit()
+    }
+
+    trace_primitive!(deserialize_bool => Bool, visit_bool(false));
+    trace_primitive!(deserialize_i8 => Int, visit_i8(0));
+    trace_primitive!(deserialize_i16 => Int, visit_i16(0));
+    trace_primitive!(deserialize_i32 => Int, visit_i32(0));
+    trace_primitive!(deserialize_i64 => Int, visit_i64(0));
+    trace_primitive!(deserialize_i128 => Int, visit_i128(0));
+    trace_primitive!(deserialize_u8 => Int, visit_u8(0));
+    trace_primitive!(deserialize_u16 => Int, visit_u16(0));
+    trace_primitive!(deserialize_u32 => Int, visit_u32(0));
+    trace_primitive!(deserialize_u64 => Int, visit_u64(0));
+    trace_primitive!(deserialize_u128 => Int, visit_u128(0));
+    trace_primitive!(deserialize_f32 => Float, visit_f32(0.0));
+    trace_primitive!(deserialize_f64 => Float, visit_f64(0.0));
+    trace_primitive!(deserialize_char => Str, visit_char('a'));
+    trace_primitive!(deserialize_str => Str, visit_borrowed_str(""));
+    trace_primitive!(deserialize_string => Str, visit_borrowed_str(""));
+    trace_primitive!(deserialize_identifier => Str, visit_borrowed_str(""));
+    trace_primitive!(deserialize_bytes => Bytes, visit_borrowed_bytes(b""));
+    trace_primitive!(deserialize_byte_buf => Bytes, visit_borrowed_bytes(b""));
+    trace_primitive!(deserialize_unit => Unit, visit_unit());
+    trace_primitive!(deserialize_ignored_any => Any, visit_unit());
+
+    fn deserialize_option<V>(self, visitor: V) -> TraceResult<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let site = self.tracer.path.join(".");
+        if let Some(format) = self.tracer.sites.get(&site) {
+            *self.format = format.clone();
+            return visitor.visit_none();
+        }
+
+        let mut inner = Format::Any;
+        self.tracer.path.push("?".into());
+        let result = visitor.visit_some(TraceDeserializer {
+            tracer: &mut *self.tracer,
+            format: &mut inner,
+        });
+        self.tracer.path.pop();
+        *self.format = Format::Option(Box::new(inner.clone()));
+        self.tracer.record_site(site, &result, &inner, self.format);
+        result
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> TraceResult<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> TraceResult<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let TraceDeserializer { tracer, format } = self;
+        let result = visitor.visit_newtype_struct(TraceDeserializer {
+            tracer,
+            format: &mut *format,
+        });
+        *format = match (name, std::mem::replace(format, Format::Any)) {
+            (as_::TUPLE_TOKEN, Format::Seq(element)) => Format::VarTuple(element),
+            (as_::SET_TOKEN, Format::Seq(element)) => Format::Set(element),
+            (as_::FROZENSET_TOKEN, Format::Seq(element)) => Format::FrozenSet(element),
+            (as_::BYTES_TOKEN, Format::Seq(_) | Format::Tuple(_)) => Format::Bytes,
+            (as_::DECIMAL_TOKEN, _) => Format::Decimal,
+            (_, format) => format,
+        };
+        result
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> TraceResult<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let site = self.tracer.path.join(".");
+        let known = self.tracer.sites.get(&site).cloned();
+        let mut elements = Vec::new();
+        let result = visitor.visit_seq(TraceSeqAccess {
+            tracer: &mut *self.tracer,
+            formats: &mut elements,
+            len: if known.is_some() { 0 } else { 1 },
+        });
+        if let Some(format) = known {
+            *self.format = format;
+            return result;
+        }
+        let element = elements.pop().unwrap_or(Format::Any);
+        *self.format = Format::Seq(Box::new(element.clone()));
+        self.tracer
+            .record_site(site, &result, &element, self.format);
+        result
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> TraceResult<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let mut elements = Vec::with_capacity(len);
+        let result = visitor.visit_seq(TraceSeqAccess {
+            tracer: &mut *self.tracer,
+            formats: &mut elements,
+            len,
+        });
+        *self.format = Format::Tuple(elements);
+        result
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> TraceResult<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> TraceResult<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let site = self.tracer.path.join(".");
+        let known = self.tracer.sites.get(&site).cloned();
+        let mut key = Format::Any;
+        let mut value = Format::Any;
+        let result = visitor.visit_map(TraceMapAccess {
+            tracer: &mut *self.tracer,
+            key: &mut key,
+            value: &mut value,
+            remaining: if known.is_some() { 0 } else { 1 },
+        });
+        if let Some(format) = known {
+            *self.format = format;
+            return result;
+        }
+        *self.format = Format::Map(Box::new(key), Box::new(value.clone()));
+        self.tracer.record_site(site, &result, &value, self.format);
+        result
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> TraceResult<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        *self.format = Format::Named(name);
+        if self.tracer.stack.contains(&name) {
+            return Err(TraceError::Recursion(name));
+        }
+
+        self.tracer.stack.push(name);
+        let mut formats = vec![Format::Any; fields.len()];
+        let skip = self.tracer.probed_field((name, None));
+        let result = visitor.visit_map(TraceStructAccess {
+            tracer: &mut *self.tracer,
+            fields,
+            formats: &mut formats,
+            index: 0,
+            skip,
+        });
+        self.tracer.stack.pop();
+        self.tracer.traced_struct((name, None), &result);
+
+        let value = result?;
+        if skip.is_none() {
+            if !self.tracer.registry.containers.contains_key(name) {
+                self.tracer.progress += 1;
+            }
+            let fields = self::fields(fields, formats);
+            self.tracer.registry.insert(name, Container::Struct(fields));
+        }
+        Ok(value)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> TraceResult<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        *self.format = Format::Named(name);
+        let tracer = self.tracer;
+        if !tracer.registry.containers.contains_key(name) {
+            let variants = variants.iter().map(|v| (*v, None)).collect();
+            tracer.registry.insert(name, Container::Enum(variants));
+        }
+        let Container::Enum(known) = &tracer.registry.containers[name] else {
+            return Err(de::Error::custom(format!(
+                "`{}` is both a struct and an enum",
+                name
+            )));
+        };
+
+        // While the enum is being traced, only pick variants which are known to terminate.
+        let route = tracer.probe.as_ref().and_then(|probe| {
+            let mut route = probe.route.iter();
+            route.find(|(enum_name, _)| *enum_name == name)
+        });
+        let index = if tracer.stack.contains(&name) {
+            (0..known.len())
+                .find(|i| tracer.terminal.contains(&(name, *i)))
+                .ok_or(TraceError::Recursion(name))?
+        } else if let Some((_, index)) = route {
+            *index
+        } else {
+            let untraced = |i: &usize| known[*i].1.is_none();
+            let mut candidates = (0..known.len()).filter(untraced);
+            candidates
+                .clone()
+                .find(|i| !tracer.deferred.contains(&(name, *i)))
+                .or_else(|| candidates.next())
+                .unwrap_or(0)
+        };
+        let Some(&variant) = variants.get(index) else {
+            return Err(de::Error::custom(format!(
+                "enum `{}` has no variants",
+                name
+            )));
+        };
+
+        tracer.stack.push(name);
+        tracer.path.push(variant.into());
+        tracer.choices.push((name, index));
+        let mut format = None;
+        let result = visitor.visit_enum(TraceEnumAccess {
+            tracer: &mut *tracer,
+            name,
+            variant,
+            format: &mut format,
+        });
+        tracer.choices.pop();
+        tracer.path.pop();
+        tracer.stack.pop();
+
+        if let (Some(format), Some(Container::Enum(known))) =
+            (format, tracer.registry.containers.get_mut(name))
+        {
+            if known[index].1.is_none() {
+                tracer.progress += 1;
+            }
+            known[index].1 = Some(format);
+        }
+        if result.is_ok() {
+            if tracer.terminal.insert((name, index)) {
+                tracer.progress += 1;
+                tracer.deferred.clear();
+            }
+        } else if let Err(TraceError::Recursion(_)) = result {
+            if tracer.deferred.insert((name, index)) {
+                tracer.progress += 1;
+            }
+        }
+        result
+    }
+}
+
+struct TraceSeqAccess<'t, 'r> {
+    tracer: &'t mut Tracer<'r>,
+    formats: &'t mut Vec<Format>,
+    len: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for TraceSeqAccess<'_, '_> {
+    type Error = TraceError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> TraceResult<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let index = self.formats.len();
+        if index == self.len {
+            return Ok(None);
+        }
+        self.formats.push(Format::Any);
+        let format = &mut self.formats[index];
+        self.tracer
+            .trace_child(index.to_string(), seed, format)
+            .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len - self.formats.len())
+    }
+}
+
+struct TraceMapAccess<'t, 'r> {
+    tracer: &'t mut Tracer<'r>,
+    key: &'t mut Format,
+    value: &'t mut Format,
+    remaining: usize,
+}
+
+impl<'de> de::MapAccess<'de> for TraceMapAccess<'_, '_> {
+    type Error = TraceError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> TraceResult<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        self.tracer.trace_child("{key}", seed, self.key).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> TraceResult<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        self.tracer.trace_child("{value}", seed, self.value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+struct TraceStructAccess<'t, 'r> {
+    tracer: &'t mut Tracer<'r>,
+    fields: &'static [&'static str],
+    formats: &'t mut [Format],
+    index: usize,
+    /// A field to leave out, see [`Probe`]
+    skip: Option<&'static str>,
+}
+
+impl<'de> de::MapAccess<'de> for TraceStructAccess<'_, '_> {
+    type Error = TraceError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> TraceResult<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.skip.is_some() && self.fields.get(self.index).copied() == self.skip {
+            self.index += 1;
+        }
+        match self.fields.get(self.index) {
+            Some(field) => seed.deserialize(field.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> TraceResult<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let index = self.index;
+        self.index += 1;
+        self.tracer
+            .trace_child(self.fields[index], seed, &mut self.formats[index])
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.fields.len() - self.index)
+    }
+}
+
+struct TraceEnumAccess<'t, 'r> {
+    tracer: &'t mut Tracer<'r>,
+    name: &'static str,
+    variant: &'static str,
+    format: &'t mut Option<VariantFormat>,
+}
+
+impl<'de, 't, 'r> de::EnumAccess<'de> for TraceEnumAccess<'t, 'r> {
+    type Error = TraceError;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> TraceResult<(V::Value, Self)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let value = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((value, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for TraceEnumAccess<'_, '_> {
+    type Error = TraceError;
+
+    fn unit_variant(self) -> TraceResult<()> {
+        *self.format = Some(VariantFormat::Unit);
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> TraceResult<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let mut format = Format::Any;
+        let result = seed
+            .deserialize(TraceDeserializer {
+                tracer: &mut *self.tracer,
+                format: &mut format,
+            })
+            .map_err(|err| self.tracer.any_error(err, &format));
+        // A variant which directly contains a recursive type is fully known even though no
+        // value could be produced for it.
+        if result.is_ok() || matches!(format, Format::Named(_)) {
+            *self.format = Some(VariantFormat::NewType(format));
+        }
+        result
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> TraceResult<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let mut elements = Vec::with_capacity(len);
+        let value = visitor.visit_seq(TraceSeqAccess {
+            tracer: self.tracer,
+            formats: &mut elements,
+            len,
+        })?;
+        *self.format = Some(VariantFormat::Tuple(elements));
+        Ok(value)
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> TraceResult<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let key = (self.name, Some(self.variant));
+        let mut formats = vec![Format::Any; fields.len()];
+        let skip = self.tracer.probed_field(key);
+        let result = visitor.visit_map(TraceStructAccess {
+            tracer: &mut *self.tracer,
+            fields,
+            formats: &mut formats,
+            index: 0,
+            skip,
+        });
+        self.tracer.traced_struct(key, &result);
+        let value = result?;
+        if skip.is_none() {
+            *self.format = Some(VariantFormat::Struct(self::fields(fields, formats)));
+        }
+        Ok(value)
+    }
+}
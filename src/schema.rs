@@ -0,0 +1,368 @@
+use serde::{ser, Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::reflect::{self, Container, Field, Format, Registry, VariantFormat};
+
+/// A JSON Schema (draft 2020-12) describing the structure of the Python objects which
+/// [`pythonize`](crate::pythonize) produces for a Rust type, and which
+/// [`depythonize`](crate::depythonize) accepts. See [`json_schema`].
+///
+/// The schema can be converted to a Python `dict` with [`pythonize`](crate::pythonize), or to any
+/// other format supported by serde (e.g. `serde_json::Value`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonSchema(Json);
+
+impl Serialize for JsonSchema {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+/// Describe the Python structure exchanged for `T` as a [`JsonSchema`].
+///
+/// Structs are objects whose fields are required unless they are an `Option` or have a default,
+/// enums are either the name of a unit variant or an object with the variant name as its single
+/// key, and tuples are arrays of fixed length. Bytes, tuples, sets and decimals, which have no
+/// JSON equivalent, are annotated with an `"x-python-type"` keyword of `"bytes"`, `"tuple"`,
+/// `"set"`, `"frozenset"` or `"decimal"`. Structs and enums are defined once in `$defs` and
+/// referred to by name.
+///
+/// The structure is discovered by tracing `T`'s `Deserialize` implementation, as for
+/// [`typing_stub`](crate::typing_stub).
+pub fn json_schema<'de, T>() -> Result<JsonSchema>
+where
+    T: Deserialize<'de>,
+{
+    let mut registry = Registry::default();
+    let root = reflect::trace::<T>(&mut registry)?;
+
+    let defs = registry
+        .containers()
+        .map(|(name, container)| (name.to_string(), container_schema(container)))
+        .collect();
+
+    let mut schema = vec![(
+        "$schema".to_string(),
+        Json::from("https://json-schema.org/draft/2020-12/schema"),
+    )];
+    let Json::Object(root) = schema_of(&root) else {
+        unreachable!("schemas are always objects")
+    };
+    schema.extend(root);
+    if registry.containers().next().is_some() {
+        schema.push(("$defs".to_string(), Json::Object(defs)));
+    }
+    Ok(JsonSchema(Json::Object(schema)))
+}
+
+/// Minimal JSON value, preserving the order of object keys
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Bool(bool),
+    Int(usize),
+    Str(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Json::Str(s.to_string())
+    }
+}
+
+impl Serialize for Json {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        match self {
+            Json::Bool(b) => serializer.serialize_bool(*b),
+            Json::Int(i) => serializer.serialize_u64(*i as u64),
+            Json::Str(s) => serializer.serialize_str(s),
+            Json::Array(items) => serializer.collect_seq(items),
+            Json::Object(entries) => serializer.collect_map(entries.iter().map(|(k, v)| (k, v))),
+        }
+    }
+}
+
+fn object<const N: usize>(entries: [(&str, Json); N]) -> Json {
+    Json::Object(
+        entries
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect(),
+    )
+}
+
+fn schema_of(format: &Format) -> Json {
+    match format {
+        Format::Any => Json::Object(Vec::new()),
+        Format::Unit => object([("type", "null".into())]),
+        Format::Bool => object([("type", "boolean".into())]),
+        Format::Int => object([("type", "integer".into())]),
+        Format::Float => object([("type", "number".into())]),
+        Format::Str => object([("type", "string".into())]),
+        Format::Bytes => object([("x-python-type", "bytes".into())]),
+        Format::Decimal => object([("x-python-type", "decimal".into())]),
+        Format::Option(inner) => object([(
+            "anyOf",
+            Json::Array(vec![schema_of(inner), schema_of(&Format::Unit)]),
+        )]),
+        Format::Seq(inner) => object([("type", "array".into()), ("items", schema_of(inner))]),
+        Format::VarTuple(inner) => object([
+            ("type", "array".into()),
+            ("x-python-type", "tuple".into()),
+            ("items", schema_of(inner)),
+        ]),
+        Format::Set(inner) => object([
+            ("type", "array".into()),
+            ("x-python-type", "set".into()),
+            ("items", schema_of(inner)),
+            ("uniqueItems", Json::Bool(true)),
+        ]),
+        Format::FrozenSet(inner) => object([
+            ("type", "array".into()),
+            ("x-python-type", "frozenset".into()),
+            ("items", schema_of(inner)),
+            ("uniqueItems", Json::Bool(true)),
+        ]),
+        Format::Tuple(elements) => object([
+            ("type", "array".into()),
+            ("x-python-type", "tuple".into()),
+            (
+                "prefixItems",
+                Json::Array(elements.iter().map(schema_of).collect()),
+            ),
+            ("minItems", Json::Int(elements.len())),
+            ("maxItems", Json::Int(elements.len())),
+        ]),
+        Format::Map(key, value) => {
+            let mut schema = object([
+                ("type", "object".into()),
+                ("additionalProperties", schema_of(value)),
+            ]);
+            if !matches!(**key, Format::Str | Format::Any) {
+                if let Json::Object(entries) = &mut schema {
+                    entries.push(("propertyNames".to_string(), schema_of(key)));
+                }
+            }
+            schema
+        }
+        Format::Named(name) => object([("$ref", Json::Str(format!("#/$defs/{}", name)))]),
+    }
+}
+
+fn struct_schema(fields: &[Field]) -> Json {
+    let properties = fields
+        .iter()
+        .map(|field| (field.name.to_string(), schema_of(&field.format)))
+        .collect();
+    let required = fields
+        .iter()
+        .filter(|field| field.required)
+        .map(|field| Json::from(field.name))
+        .collect();
+    object([
+        ("type", "object".into()),
+        ("properties", Json::Object(properties)),
+        ("required", Json::Array(required)),
+    ])
+}
+
+fn container_schema(container: &Container) -> Json {
+    match container {
+        Container::Struct(fields) => struct_schema(fields),
+        Container::Enum(variants) => {
+            let mut units = Vec::new();
+            let mut tagged = Vec::new();
+            for (variant, format) in variants {
+                let value = match format {
+                    None | Some(VariantFormat::Unit) => {
+                        units.push(Json::from(*variant));
+                        continue;
+                    }
+                    Some(VariantFormat::NewType(format)) => schema_of(format),
+                    Some(VariantFormat::Tuple(formats)) => {
+                        schema_of(&Format::Tuple(formats.clone()))
+                    }
+                    Some(VariantFormat::Struct(fields)) => struct_schema(fields),
+                };
+                tagged.push(object([
+                    ("type", "object".into()),
+                    ("properties", object([(variant, value)])),
+                    ("required", Json::Array(vec![Json::from(*variant)])),
+                    ("additionalProperties", Json::Bool(false)),
+                ]));
+            }
+            if !units.is_empty() {
+                tagged.insert(
+                    0,
+                    object([("type", "string".into()), ("enum", Json::Array(units))]),
+                );
+            }
+            match tagged.len() {
+                1 => tagged.pop().unwrap(),
+                _ => object([("oneOf", Json::Array(tagged))]),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::json_schema;
+    use crate::pythonize;
+    use pyo3::prelude::*;
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[allow(dead_code)]
+    #[derive(Deserialize)]
+    struct Shapes {
+        name: String,
+        shapes: Vec<Shape>,
+        scale: Option<f64>,
+        counts: HashMap<u8, (bool, u32)>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Polygon {
+            #[serde(with = "serde_bytes")]
+            data: Vec<u8>,
+        },
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let schema = serde_json::to_value(json_schema::<Shapes>().unwrap()).unwrap();
+        assert_eq!(
+            schema,
+            json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "$ref": "#/$defs/Shapes",
+                "$defs": {
+                    "Shape": {
+                        "oneOf": [
+                            {"type": "string", "enum": ["Empty"]},
+                            {
+                                "type": "object",
+                                "properties": {"Circle": {"type": "number"}},
+                                "required": ["Circle"],
+                                "additionalProperties": false
+                            },
+                            {
+                                "type": "object",
+                                "properties": {
+                                    "Polygon": {
+                                        "type": "object",
+                                        "properties": {"data": {"x-python-type": "bytes"}},
+                                        "required": ["data"]
+                                    }
+                                },
+                                "required": ["Polygon"],
+                                "additionalProperties": false
+                            }
+                        ]
+                    },
+                    "Shapes": {
+                        "type": "object",
+                        "properties": {
+                            "name": {"type": "string"},
+                            "shapes": {"type": "array", "items": {"$ref": "#/$defs/Shape"}},
+                            "scale": {"anyOf": [{"type": "number"}, {"type": "null"}]},
+                            "counts": {
+                                "type": "object",
+                                "additionalProperties": {
+                                    "type": "array",
+                                    "x-python-type": "tuple",
+                                    "prefixItems": [{"type": "boolean"}, {"type": "integer"}],
+                                    "minItems": 2,
+                                    "maxItems": 2
+                                },
+                                "propertyNames": {"type": "integer"}
+                            }
+                        },
+                        "required": ["name", "shapes", "counts"]
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_json_schema_required() {
+        #[allow(dead_code)]
+        #[derive(Deserialize)]
+        struct Settings {
+            name: String,
+            #[serde(default)]
+            retries: u32,
+            #[serde(deserialize_with = "Option::deserialize")]
+            timeout: Option<f64>,
+            mode: Mode,
+            children: Vec<Settings>,
+        }
+
+        #[allow(dead_code)]
+        #[derive(Deserialize)]
+        enum Mode {
+            Off,
+            Fast(u8),
+            Custom {
+                level: u8,
+                #[serde(default)]
+                label: String,
+            },
+        }
+
+        let schema = serde_json::to_value(json_schema::<Settings>().unwrap()).unwrap();
+        let defs = &schema["$defs"];
+        assert_eq!(
+            defs["Settings"]["required"],
+            json!(["name", "timeout", "mode", "children"])
+        );
+        assert_eq!(
+            defs["Mode"]["oneOf"][2]["properties"]["Custom"]["required"],
+            json!(["level"])
+        );
+    }
+
+    #[test]
+    fn test_json_schema_unnamed_root() {
+        let schema = serde_json::to_value(json_schema::<Vec<i32>>().unwrap()).unwrap();
+        assert_eq!(
+            schema,
+            json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "array",
+                "items": {"type": "integer"}
+            })
+        );
+    }
+
+    #[test]
+    fn test_json_schema_to_python() {
+        Python::attach(|py| {
+            let schema = pythonize(py, &json_schema::<Shapes>().unwrap()).unwrap();
+            let defs = schema.get_item("$defs").unwrap();
+            let required = defs
+                .get_item("Shapes")
+                .unwrap()
+                .get_item("required")
+                .unwrap();
+            assert_eq!(
+                required.repr().unwrap().to_string(),
+                "['name', 'shapes', 'counts']"
+            );
+        });
+    }
+}
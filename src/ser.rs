@@ -1,15 +1,24 @@
+use std::fmt;
 use std::marker::PhantomData;
+use std::ops::RangeInclusive;
+use std::sync::{Mutex, TryLockError};
 
-#[cfg(feature = "arbitrary_precision")]
-use pyo3::types::{PyAnyMethods, PyFloat, PyInt};
+use pyo3::exceptions::{PyAttributeError, PyRuntimeError, PyValueError};
+use pyo3::sync::PyOnceLock;
 use pyo3::types::{
-    PyDict, PyDictMethods, PyList, PyListMethods, PyMapping, PySequence, PyString, PyTuple,
-    PyTupleMethods,
+    PyAnyMethods, PyByteArray, PyBytes, PyCFunction, PyDict, PyDictMethods, PyFloat, PyFrozenSet,
+    PyInt, PyList, PyListMethods, PyMapping, PyMemoryView, PySequence, PySet, PyString, PyTuple,
+    PyTupleMethods, PyType, PyTypeMethods,
 };
-use pyo3::{Bound, BoundObject, IntoPyObject, PyAny, PyResult, Python};
+use pyo3::{intern, Bound, BoundObject, IntoPyObject, Py, PyAny, PyErr, PyResult, Python};
 use serde::{ser, Serialize};
 
+use crate::as_;
+use crate::case::FieldCase;
 use crate::error::{PythonizeError, Result};
+use crate::namedtuple::{namedtuple_class, NamedTupleFields};
+use crate::object::{take_serialized, OBJECT_TOKEN};
+use crate::value::INT_TOKEN;
 
 /// Trait for types which can represent a Python mapping
 pub trait PythonizeMappingType {
@@ -73,6 +82,28 @@ pub trait PythonizeTypes {
     type NamedMap: PythonizeNamedMappingType;
     /// Python sequence type (should be representable as python sequence)
     type List: PythonizeListType;
+
+    /// Create the Python sequence for tuples, tuple structs and tuple variants, e.g. to produce
+    /// lists instead of tuples. The sequences must be hashable to be used as mapping keys.
+    fn create_tuple<'py, T, U>(
+        py: Python<'py>,
+        elements: impl IntoIterator<Item = T, IntoIter = U>,
+    ) -> PyResult<Bound<'py, PySequence>>
+    where
+        T: IntoPyObject<'py>,
+        U: ExactSizeIterator<Item = T>,
+    {
+        PyTuple::create_sequence(py, elements)
+    }
+
+    /// Create the Python object for bytes, e.g. to produce a `bytearray` instead of `bytes`.
+    ///
+    /// Serde only lends the bytes for the duration of the call, so the object must copy them: a
+    /// zero-copy `memoryview` over a Rust-owned buffer cannot be produced here. Such an object can
+    /// be created beforehand and embedded with [`PyObjectWrapper`](crate::PyObjectWrapper).
+    fn create_bytes<'py>(py: Python<'py>, bytes: &[u8]) -> PyResult<Bound<'py, PyAny>> {
+        Ok(PyBytes::new(py, bytes).into_any())
+    }
 }
 
 impl PythonizeMappingType for PyDict {
@@ -182,17 +213,110 @@ where
     value.serialize(Pythonizer::custom::<P>(py))
 }
 
+/// Convert the items of `iter` to Python objects lazily, returning a Python iterator which
+/// serializes each item only when it is requested.
+///
+/// This avoids materialising a large sequence as a `list`. The iterator is moved into the returned
+/// object, so it must be `Send` and `'static`; errors from serialization are raised from
+/// `__next__`.
+pub fn pythonize_iter<'py, I>(py: Python<'py>, iter: I) -> Result<Bound<'py, PyAny>>
+where
+    I: IntoIterator,
+    I::IntoIter: Send + 'static,
+    I::Item: Serialize,
+{
+    Pythonizer::new(py).serialize_iter(iter)
+}
+
+/// Insert the entries of a map or struct into an existing `dict`, overwriting existing keys, e.g.
+/// to merge several values into one `kwargs` dict.
+///
+/// Only maps and structs (including newtype structs wrapping them) are accepted. If an error
+/// occurs, the entries inserted before it are kept.
+pub fn pythonize_into_dict<T>(dict: &Bound<'_, PyDict>, value: &T) -> Result<()>
+where
+    T: ?Sized + Serialize,
+{
+    Pythonizer::new(dict.py()).serialize_into_dict(dict, value)
+}
+
+/// Set the fields of a struct as attributes of an existing Python object with `setattr`, e.g. to
+/// update a `#[pyclass]` or dataclass instance in place.
+///
+/// ```
+/// use pyo3::prelude::*;
+/// use pythonize::pythonize_onto;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Position {
+///     x: f64,
+///     y: f64,
+/// }
+///
+/// Python::attach(|py| {
+///     let obj = py.eval(c"type('Point', (), {'x': 0.0, 'y': 0.0})()", None, None).unwrap();
+///     pythonize_onto(&obj, &Position { x: 1.0, y: 2.5 }).unwrap();
+///     assert_eq!(obj.getattr("y").unwrap().extract::<f64>().unwrap(), 2.5);
+/// })
+/// ```
+///
+/// Maps with string keys are accepted too, e.g. for `#[serde(flatten)]`. Attributes are created
+/// if needed; use [`Pythonizer::with_existing_attributes`] to only allow existing ones. If an
+/// error occurs, the attributes set before it keep their new value.
+pub fn pythonize_onto<T>(obj: &Bound<'_, PyAny>, value: &T) -> Result<()>
+where
+    T: ?Sized + Serialize,
+{
+    Pythonizer::new(obj.py()).serialize_onto(obj, value)
+}
+
+/// Convert the items of `iter` to Python objects and append them to an existing `list`.
+///
+/// If an error occurs, the items appended before it are kept.
+pub fn pythonize_extend<I>(list: &Bound<'_, PyList>, iter: I) -> Result<()>
+where
+    I: IntoIterator,
+    I::Item: Serialize,
+{
+    Pythonizer::new(list.py()).serialize_extend(list, iter)
+}
+
 /// A structure that serializes Rust values into Python objects
-#[derive(Clone, Copy)]
 pub struct Pythonizer<'py, P> {
     py: Python<'py>,
+    options: PythonizerOptions,
     _types: PhantomData<P>,
 }
 
+/// Behavioural switches of a [`Pythonizer`], shared by all nested serializers
+#[derive(Clone, Copy, Default)]
+struct PythonizerOptions {
+    named_tuples: bool,
+    byte_sequences: bool,
+    json_keys: bool,
+    existing_attributes: bool,
+    field_case: FieldCase,
+    skip_none_fields: bool,
+    #[cfg(feature = "numpy")]
+    numpy_arrays: bool,
+    /// Wrap structs and enum variants in `dict` subclasses, see [`Pythonizer::for_merge`]
+    marked: bool,
+}
+
+impl<P> Clone for Pythonizer<'_, P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P> Copy for Pythonizer<'_, P> {}
+
 impl<'py, P> From<Python<'py>> for Pythonizer<'py, P> {
     fn from(py: Python<'py>) -> Self {
         Self {
             py,
+            options: PythonizerOptions::default(),
             _types: PhantomData,
         }
     }
@@ -210,11 +334,170 @@ impl<'py> Pythonizer<'py, PythonizeDefault> {
     }
 }
 
+impl<P> Pythonizer<'_, P> {
+    /// Serialize structs and tuple structs as `collections.namedtuple` instances instead of
+    /// mappings and sequences.
+    ///
+    /// The namedtuple class is looked up by the Rust type name among classes registered with
+    /// [`register_namedtuple`](crate::register_namedtuple). If none is registered, a class is
+    /// created (and cached) with the struct's field names, or `_0`, `_1`, ... for tuple structs.
+    pub fn with_named_tuples(mut self, enabled: bool) -> Self {
+        self.options.named_tuples = enabled;
+        self
+    }
+
+    /// Serialize non-empty sequences and tuples whose elements are all `u8` (such as `Vec<u8>` or
+    /// `[u8; 32]`) as `bytes` instead of lists or tuples of integers, without needing
+    /// `serde_bytes`. Tuple structs and tuple variants are left as they are.
+    pub fn with_byte_sequences(mut self, enabled: bool) -> Self {
+        self.options.byte_sequences = enabled;
+        self
+    }
+
+    /// Convert mapping keys to strings in the same way as `serde_json`, so that e.g. a
+    /// `HashMap<u32, T>` becomes a `dict` with `str` keys, and fail for keys which `serde_json`
+    /// rejects (such as tuples). By default keys are converted like any other value.
+    pub fn with_json_keys(mut self, enabled: bool) -> Self {
+        self.options.json_keys = enabled;
+        self
+    }
+
+    /// Convert the names of struct fields to the given case (e.g. `maxRetries` for a
+    /// `max_retries` field) in the mappings produced for structs and struct variants. Fields of
+    /// namedtuples keep their Rust names, as do the fields of `#[serde(flatten)]` structs, which
+    /// serde writes as map entries.
+    pub fn with_field_case(mut self, case: FieldCase) -> Self {
+        self.options.field_case = case;
+        self
+    }
+
+    /// Leave out struct fields whose value is `Option::None` from the mappings produced for structs
+    /// and struct variants, as if every field had
+    /// `#[serde(skip_serializing_if = "Option::is_none")]`. Other fields which become Python
+    /// `None`, such as `()` or unit structs, are kept, as are all the fields of namedtuples.
+    pub fn with_skip_none_fields(mut self, enabled: bool) -> Self {
+        self.options.skip_none_fields = enabled;
+        self
+    }
+
+    /// Only set attributes which already exist on the object in
+    /// [`serialize_onto`](Self::serialize_onto), and raise `AttributeError` for other fields
+    /// (e.g. to catch typos or mismatched types). By default attributes are created as needed.
+    pub fn with_existing_attributes(mut self, enabled: bool) -> Self {
+        self.options.existing_attributes = enabled;
+        self
+    }
+
+    /// Serialize sequences whose elements are all numbers, or all booleans, as NumPy arrays
+    /// (with `numpy.array`) instead of lists. Empty sequences are still serialized as lists.
+    #[cfg(feature = "numpy")]
+    pub fn with_numpy_arrays(mut self, enabled: bool) -> Self {
+        self.options.numpy_arrays = enabled;
+        self
+    }
+}
+
+impl<'py, P: PythonizeTypes> Pythonizer<'py, P> {
+    /// Insert the entries of a map or struct into `dict`, with the options of this serializer.
+    /// See [`pythonize_into_dict`].
+    pub fn serialize_into_dict<T>(self, dict: &Bound<'py, PyDict>, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(EntriesSerializer {
+            target: EntriesTarget::Dict(dict),
+            pythonizer: self,
+            key: None,
+        })
+    }
+
+    /// Set the fields of a struct (or the entries of a map with string keys) as attributes of
+    /// `obj`, with the options of this serializer. See [`pythonize_onto`].
+    pub fn serialize_onto<T>(self, obj: &Bound<'py, PyAny>, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(EntriesSerializer {
+            target: EntriesTarget::Attributes(obj),
+            pythonizer: self,
+            key: None,
+        })
+    }
+
+    /// Append the items of `iter` to `list`, with the options of this serializer. See
+    /// [`pythonize_extend`].
+    pub fn serialize_extend<I>(self, list: &Bound<'py, PyList>, iter: I) -> Result<()>
+    where
+        I: IntoIterator,
+        I::Item: Serialize,
+    {
+        for item in iter {
+            list.append(item.serialize(self)?)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'py, P: PythonizeTypes + 'static> Pythonizer<'py, P> {
+    /// Convert the items of `iter` to Python objects lazily, with the options of this
+    /// serializer. See [`pythonize_iter`].
+    pub fn serialize_iter<I>(self, iter: I) -> Result<Bound<'py, PyAny>>
+    where
+        I: IntoIterator,
+        I::IntoIter: Send + 'static,
+        I::Item: Serialize,
+    {
+        let py = self.py;
+        let options = self.options;
+        let iter = Mutex::new(iter.into_iter());
+        // `iter(callable, sentinel)` calls `next_item` until it returns the sentinel, which must
+        // be distinct from any item (e.g. `None`)
+        let builtins = py.import(intern!(py, "builtins"))?;
+        let sentinel = builtins.getattr(intern!(py, "object"))?.call0()?;
+        let exhausted = sentinel.clone().unbind();
+
+        let next_item = move |args: &Bound<'_, PyTuple>, _: Option<&Bound<'_, PyDict>>| {
+            let py = args.py();
+            let item = match iter.try_lock() {
+                Ok(mut iter) => iter.next(),
+                Err(TryLockError::WouldBlock) => {
+                    return Err(PyValueError::new_err("iterator already executing"))
+                }
+                Err(TryLockError::Poisoned(_)) => {
+                    return Err(PyRuntimeError::new_err("iterator panicked"))
+                }
+            };
+            match item {
+                Some(item) => {
+                    let pythonizer = Pythonizer::<P> {
+                        py,
+                        options,
+                        _types: PhantomData,
+                    };
+                    Ok(item.serialize(pythonizer)?.unbind())
+                }
+                None => Ok(exhausted.clone_ref(py)),
+            }
+        };
+        let next_item = PyCFunction::new_closure(py, Some(c"pythonize_iter"), None, next_item)?;
+        Ok(builtins
+            .getattr(intern!(py, "iter"))?
+            .call1((next_item, sentinel))?)
+    }
+}
+
 #[doc(hidden)]
 pub struct PythonCollectionSerializer<'py, P> {
     items: Vec<Bound<'py, PyAny>>,
-    py: Python<'py>,
-    _types: PhantomData<P>,
+    /// The elements so far, while they are all `u8` and byte sequences are enabled
+    bytes: Option<Vec<u8>>,
+    pythonizer: Pythonizer<'py, P>,
+}
+
+#[doc(hidden)]
+pub struct PythonTupleStructSerializer<'py, P> {
+    name: &'static str,
+    inner: PythonCollectionSerializer<'py, P>,
 }
 
 #[doc(hidden)]
@@ -231,34 +514,36 @@ pub struct PythonStructVariantSerializer<'py, P: PythonizeTypes> {
     inner: PythonStructDictSerializer<'py, P>,
 }
 
-#[cfg(feature = "arbitrary_precision")]
 #[doc(hidden)]
 pub enum StructSerializer<'py, P: PythonizeTypes> {
     Struct(PythonStructDictSerializer<'py, P>),
+    NamedTuple(PythonNamedTupleSerializer<'py, P>),
+    #[cfg(feature = "arbitrary_precision")]
     Number {
         py: Python<'py>,
         number_string: Option<String>,
-        _types: PhantomData<P>,
     },
 }
 
-#[cfg(not(feature = "arbitrary_precision"))]
-#[doc(hidden)]
-pub type StructSerializer<'py, P> = PythonStructDictSerializer<'py, P>;
-
 #[doc(hidden)]
 pub struct PythonStructDictSerializer<'py, P: PythonizeTypes> {
-    py: Python<'py>,
+    pythonizer: Pythonizer<'py, P>,
     builder: <P::NamedMap as PythonizeNamedMappingType>::Builder<'py>,
-    _types: PhantomData<P>,
+}
+
+#[doc(hidden)]
+pub struct PythonNamedTupleSerializer<'py, P> {
+    pythonizer: Pythonizer<'py, P>,
+    name: &'static str,
+    fields: Vec<&'static str>,
+    values: Vec<Bound<'py, PyAny>>,
 }
 
 #[doc(hidden)]
 pub struct PythonMapSerializer<'py, P: PythonizeTypes> {
-    py: Python<'py>,
+    pythonizer: Pythonizer<'py, P>,
     builder: <P::Map as PythonizeMappingType>::Builder<'py>,
     key: Option<Bound<'py, PyAny>>,
-    _types: PhantomData<P>,
 }
 
 impl<'py, P: PythonizeTypes> Pythonizer<'py, P> {
@@ -273,6 +558,124 @@ impl<'py, P: PythonizeTypes> Pythonizer<'py, P> {
             .map(|x| x.into_any().into_bound())
             .map_err(Into::into)
     }
+
+    /// Convert a mapping key, as a string if [`with_json_keys`](Self::with_json_keys) is enabled
+    fn serialize_map_key<T>(self, key: &T) -> Result<Bound<'py, PyAny>>
+    where
+        T: ?Sized + Serialize,
+    {
+        if self.options.json_keys {
+            Ok(PyString::new(self.py, &key.serialize(MapKeySerializer)?).into_any())
+        } else {
+            key.serialize(self)
+        }
+    }
+
+    /// Convert the value of a map entry whose key was converted by `serialize_key`, shared by
+    /// all the serializers of maps
+    fn serialize_map_entry<T>(
+        self,
+        key: &mut Option<Bound<'py, PyAny>>,
+        value: &T,
+    ) -> Result<(Bound<'py, PyAny>, Bound<'py, PyAny>)>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = key
+            .take()
+            .expect("serialize_value should always be called after serialize_key");
+        Ok((key, value.serialize(self)?))
+    }
+
+    /// Convert a struct field to its name and value, or `None` if it is left out, shared by all
+    /// the serializers of structs (see [`with_field_case`](Self::with_field_case) and
+    /// [`with_skip_none_fields`](Self::with_skip_none_fields))
+    fn serialize_struct_field<T>(
+        self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<Option<(Bound<'py, PyString>, Bound<'py, PyAny>)>>
+    where
+        T: ?Sized + Serialize,
+    {
+        if self.options.skip_none_fields && matches!(value.serialize(NoneSerializer), Ok(true)) {
+            return Ok(None);
+        }
+        let value = value.serialize(self)?;
+        let key = self.options.field_case.apply(key);
+        Ok(Some((PyString::new(self.py, &key), value)))
+    }
+
+    /// Wrap the value of an enum variant as `{variant: value}`
+    fn serialize_variant(
+        self,
+        name: &'static str,
+        variant: &'static str,
+        value: Bound<'py, PyAny>,
+    ) -> Result<Bound<'py, PyAny>> {
+        let mut m = P::NamedMap::builder(self.py, 1, name)?;
+        P::NamedMap::push_field(&mut m, PyString::new(self.py, variant), value)?;
+        let dict = P::NamedMap::finish(m)?.into_any();
+        if self.options.marked {
+            return Ok(marker_class(self.py, &VARIANT, "Variant")?.call1((dict,))?);
+        }
+        Ok(dict)
+    }
+}
+
+impl<'py> Pythonizer<'py, PythonizeDefault> {
+    /// A serializer for the current value in [`depythonize_into`](crate::depythonize_into), which
+    /// names fields in `field_case` as they are read back, and wraps structs and enum variants in
+    /// `dict` subclasses (see [`Marked`]) so that they can be merged accordingly.
+    pub(crate) fn for_merge(py: Python<'py>, field_case: FieldCase) -> Self {
+        let mut pythonizer = Self::new(py).with_field_case(field_case);
+        pythonizer.options.marked = true;
+        pythonizer
+    }
+}
+
+/// The `dict` subclass of the structs serialized by [`Pythonizer::for_merge`]
+static STRUCT: PyOnceLock<Py<PyType>> = PyOnceLock::new();
+/// The `dict` subclass of the enum variants serialized by [`Pythonizer::for_merge`]
+static VARIANT: PyOnceLock<Py<PyType>> = PyOnceLock::new();
+
+fn marker_class<'a, 'py>(
+    py: Python<'py>,
+    cell: &'a PyOnceLock<Py<PyType>>,
+    name: &str,
+) -> PyResult<&'a Bound<'py, PyType>> {
+    cell.get_or_try_init(py, || {
+        let class =
+            py.get_type::<PyType>()
+                .call1((name, (py.get_type::<PyDict>(),), PyDict::new(py)))?;
+        Ok::<_, PyErr>(class.cast_into::<PyType>()?.unbind())
+    })
+    .map(|class| class.bind(py))
+}
+
+/// What a `dict` serialized by [`Pythonizer::for_merge`] holds
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Marked {
+    /// The fields of a struct
+    Struct,
+    /// An enum variant, as `{variant: value}`
+    Variant,
+    /// Anything else, e.g. the entries of a map
+    Other,
+}
+
+impl Marked {
+    pub(crate) fn of(obj: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let py = obj.py();
+        let class = obj.get_type();
+        if class.is(marker_class(py, &STRUCT, "Struct")?) {
+            Ok(Marked::Struct)
+        } else if class.is(marker_class(py, &VARIANT, "Variant")?) {
+            Ok(Marked::Variant)
+        } else {
+            Ok(Marked::Other)
+        }
+    }
 }
 
 impl<'py, P: PythonizeTypes> ser::Serializer for Pythonizer<'py, P> {
@@ -280,7 +683,7 @@ impl<'py, P: PythonizeTypes> ser::Serializer for Pythonizer<'py, P> {
     type Error = PythonizeError;
     type SerializeSeq = PythonCollectionSerializer<'py, P>;
     type SerializeTuple = PythonCollectionSerializer<'py, P>;
-    type SerializeTupleStruct = PythonCollectionSerializer<'py, P>;
+    type SerializeTupleStruct = PythonTupleStructSerializer<'py, P>;
     type SerializeTupleVariant = PythonTupleVariantSerializer<'py, P>;
     type SerializeMap = PythonMapSerializer<'py, P>;
     type SerializeStruct = StructSerializer<'py, P>;
@@ -306,6 +709,10 @@ impl<'py, P: PythonizeTypes> ser::Serializer for Pythonizer<'py, P> {
         self.serialise_default(v)
     }
 
+    fn serialize_i128(self, v: i128) -> Result<Bound<'py, PyAny>> {
+        self.serialise_default(v)
+    }
+
     fn serialize_u8(self, v: u8) -> Result<Bound<'py, PyAny>> {
         self.serialise_default(v)
     }
@@ -322,6 +729,10 @@ impl<'py, P: PythonizeTypes> ser::Serializer for Pythonizer<'py, P> {
         self.serialise_default(v)
     }
 
+    fn serialize_u128(self, v: u128) -> Result<Bound<'py, PyAny>> {
+        self.serialise_default(v)
+    }
+
     fn serialize_f32(self, v: f32) -> Result<Bound<'py, PyAny>> {
         self.serialise_default(v)
     }
@@ -339,7 +750,7 @@ impl<'py, P: PythonizeTypes> ser::Serializer for Pythonizer<'py, P> {
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Bound<'py, PyAny>> {
-        self.serialise_default(v)
+        Ok(P::create_bytes(self.py, v)?)
     }
 
     fn serialize_none(self) -> Result<Bound<'py, PyAny>> {
@@ -370,15 +781,62 @@ impl<'py, P: PythonizeTypes> ser::Serializer for Pythonizer<'py, P> {
         self.serialize_str(variant)
     }
 
-    fn serialize_newtype_struct<T>(
-        self,
-        _name: &'static str,
-        value: &T,
-    ) -> Result<Bound<'py, PyAny>>
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Bound<'py, PyAny>>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(self)
+        match name {
+            as_::SET_TOKEN => {
+                let items = value.serialize(self)?.try_iter()?;
+                let items = items.collect::<PyResult<Vec<_>>>()?;
+                Ok(PySet::new(self.py, items)?.into_any())
+            }
+            as_::FROZENSET_TOKEN => {
+                let items = value.serialize(self)?.try_iter()?;
+                let items = items.collect::<PyResult<Vec<_>>>()?;
+                Ok(PyFrozenSet::new(self.py, items)?.into_any())
+            }
+            OBJECT_TOKEN => {
+                let obj = take_serialized()
+                    .ok_or_else(|| PythonizeError::msg("missing Python object to serialize"))?;
+                Ok(obj.into_bound(self.py))
+            }
+            INT_TOKEN => {
+                let digits = value.serialize(self)?;
+                Ok(self.py.get_type::<PyInt>().call1((digits,))?)
+            }
+            as_::TUPLE_TOKEN => {
+                let items = value.serialize(self)?.try_iter()?;
+                let items = items.collect::<PyResult<Vec<_>>>()?;
+                Ok(P::create_tuple(self.py, items)?.into_any())
+            }
+            as_::BYTES_TOKEN => {
+                let obj = value.serialize(self)?;
+                if obj.is_instance_of::<PyBytes>()
+                    || obj.is_instance_of::<PyByteArray>()
+                    || obj.is_instance_of::<PyMemoryView>()
+                {
+                    return Ok(obj);
+                }
+                let bytes = obj.try_iter()?;
+                let bytes = bytes
+                    .map(|byte| byte?.extract::<u8>())
+                    .collect::<PyResult<Vec<_>>>()?;
+                Ok(P::create_bytes(self.py, &bytes)?)
+            }
+            as_::DECIMAL_TOKEN => {
+                let mut obj = value.serialize(self)?;
+                if obj.is_instance_of::<PyFloat>() {
+                    obj = obj.str()?.into_any();
+                }
+                let decimal = self
+                    .py
+                    .import(intern!(self.py, "decimal"))?
+                    .getattr(intern!(self.py, "Decimal"))?;
+                Ok(decimal.call1((obj,))?)
+            }
+            _ => value.serialize(self),
+        }
     }
 
     fn serialize_newtype_variant<T>(
@@ -391,13 +849,7 @@ impl<'py, P: PythonizeTypes> ser::Serializer for Pythonizer<'py, P> {
     where
         T: ?Sized + Serialize,
     {
-        let mut m = P::NamedMap::builder(self.py, 1, name)?;
-        P::NamedMap::push_field(
-            &mut m,
-            PyString::new(self.py, variant),
-            value.serialize(self)?,
-        )?;
-        Ok(P::NamedMap::finish(m)?.into_any())
+        self.serialize_variant(name, variant, value.serialize(self)?)
     }
 
     fn serialize_seq(self, len: Option<usize>) -> Result<PythonCollectionSerializer<'py, P>> {
@@ -407,25 +859,33 @@ impl<'py, P: PythonizeTypes> ser::Serializer for Pythonizer<'py, P> {
         };
         Ok(PythonCollectionSerializer {
             items,
-            py: self.py,
-            _types: PhantomData,
+            bytes: self.options.byte_sequences.then(Vec::new),
+            pythonizer: self,
         })
     }
 
     fn serialize_tuple(self, len: usize) -> Result<PythonCollectionSerializer<'py, P>> {
+        // Arrays such as `[u8; 32]` are serialized as tuples
         Ok(PythonCollectionSerializer {
             items: Vec::with_capacity(len),
-            py: self.py,
-            _types: PhantomData,
+            bytes: self.options.byte_sequences.then(Vec::new),
+            pythonizer: self,
         })
     }
 
     fn serialize_tuple_struct(
         self,
-        _name: &'static str,
+        name: &'static str,
         len: usize,
-    ) -> Result<PythonCollectionSerializer<'py, P>> {
-        self.serialize_tuple(len)
+    ) -> Result<PythonTupleStructSerializer<'py, P>> {
+        Ok(PythonTupleStructSerializer {
+            name,
+            inner: PythonCollectionSerializer {
+                items: Vec::with_capacity(len),
+                bytes: None,
+                pythonizer: self,
+            },
+        })
     }
 
     fn serialize_tuple_variant(
@@ -435,7 +895,11 @@ impl<'py, P: PythonizeTypes> ser::Serializer for Pythonizer<'py, P> {
         variant: &'static str,
         len: usize,
     ) -> Result<PythonTupleVariantSerializer<'py, P>> {
-        let inner = self.serialize_tuple(len)?;
+        let inner = PythonCollectionSerializer {
+            items: Vec::with_capacity(len),
+            bytes: None,
+            pythonizer: self,
+        };
         Ok(PythonTupleVariantSerializer {
             name,
             variant,
@@ -447,39 +911,34 @@ impl<'py, P: PythonizeTypes> ser::Serializer for Pythonizer<'py, P> {
         Ok(PythonMapSerializer {
             builder: P::Map::builder(self.py, len)?,
             key: None,
-            py: self.py,
-            _types: PhantomData,
+            pythonizer: self,
         })
     }
 
     fn serialize_struct(self, name: &'static str, len: usize) -> Result<StructSerializer<'py, P>> {
+        // With arbitrary_precision enabled, a serde_json::Number serializes as a "$serde_json::private::Number"
+        // struct with a "$serde_json::private::Number" field, whose value is the String in Number::n.
         #[cfg(feature = "arbitrary_precision")]
-        {
-            // With arbitrary_precision enabled, a serde_json::Number serializes as a "$serde_json::private::Number"
-            // struct with a "$serde_json::private::Number" field, whose value is the String in Number::n.
-            if name == "$serde_json::private::Number" && len == 1 {
-                return Ok(StructSerializer::Number {
-                    py: self.py,
-                    number_string: None,
-                    _types: PhantomData,
-                });
-            }
-
-            Ok(StructSerializer::Struct(PythonStructDictSerializer {
+        if name == "$serde_json::private::Number" && len == 1 {
+            return Ok(StructSerializer::Number {
                 py: self.py,
-                builder: P::NamedMap::builder(self.py, len, name)?,
-                _types: PhantomData,
-            }))
+                number_string: None,
+            });
         }
 
-        #[cfg(not(feature = "arbitrary_precision"))]
-        {
-            Ok(PythonStructDictSerializer {
-                py: self.py,
-                builder: P::NamedMap::builder(self.py, len, name)?,
-                _types: PhantomData,
-            })
+        if self.options.named_tuples {
+            return Ok(StructSerializer::NamedTuple(PythonNamedTupleSerializer {
+                pythonizer: self,
+                name,
+                fields: Vec::with_capacity(len),
+                values: Vec::with_capacity(len),
+            }));
         }
+
+        Ok(StructSerializer::Struct(PythonStructDictSerializer {
+            pythonizer: self,
+            builder: P::NamedMap::builder(self.py, len, name)?,
+        }))
     }
 
     fn serialize_struct_variant(
@@ -493,9 +952,8 @@ impl<'py, P: PythonizeTypes> ser::Serializer for Pythonizer<'py, P> {
             name,
             variant,
             inner: PythonStructDictSerializer {
-                py: self.py,
+                pythonizer: self,
                 builder: P::NamedMap::builder(self.py, len, variant)?,
-                _types: PhantomData,
             },
         })
     }
@@ -509,13 +967,35 @@ impl<'py, P: PythonizeTypes> ser::SerializeSeq for PythonCollectionSerializer<'p
     where
         T: ?Sized + Serialize,
     {
-        self.items.push(pythonize_custom::<P, _>(self.py, value)?);
+        if let Some(bytes) = &mut self.bytes {
+            if let Ok(byte) = value.serialize(ByteSerializer) {
+                bytes.push(byte);
+                return Ok(());
+            }
+            // Not a sequence of bytes after all, so fall back to a list
+            self.items = bytes
+                .iter()
+                .map(|byte| byte.serialize(self.pythonizer))
+                .collect::<Result<_>>()?;
+            self.bytes = None;
+        }
+        self.items.push(value.serialize(self.pythonizer)?);
         Ok(())
     }
 
     fn end(self) -> Result<Bound<'py, PyAny>> {
-        let instance = P::List::create_sequence(self.py, self.items)?;
-        Ok(instance.into_pyobject(self.py)?.into_any())
+        let py = self.pythonizer.py;
+        if let Some(bytes) = self.bytes.filter(|bytes| !bytes.is_empty()) {
+            return ser::Serializer::serialize_bytes(self.pythonizer, &bytes);
+        }
+        #[cfg(feature = "numpy")]
+        if self.pythonizer.options.numpy_arrays {
+            if let Some(array) = crate::numpy::to_ndarray(py, &self.items)? {
+                return Ok(array);
+            }
+        }
+        let instance = P::List::create_sequence(py, self.items)?;
+        Ok(instance.into_pyobject(py)?.into_any())
     }
 }
 
@@ -531,11 +1011,16 @@ impl<'py, P: PythonizeTypes> ser::SerializeTuple for PythonCollectionSerializer<
     }
 
     fn end(self) -> Result<Bound<'py, PyAny>> {
-        Ok(PyTuple::new(self.py, self.items)?.into_any())
+        let py = self.pythonizer.py;
+        if let Some(bytes) = self.bytes.filter(|bytes| !bytes.is_empty()) {
+            return ser::Serializer::serialize_bytes(self.pythonizer, &bytes);
+        }
+        let instance = P::create_tuple(py, self.items)?;
+        Ok(instance.into_pyobject(py)?.into_any())
     }
 }
 
-impl<'py, P: PythonizeTypes> ser::SerializeTupleStruct for PythonCollectionSerializer<'py, P> {
+impl<'py, P: PythonizeTypes> ser::SerializeTupleStruct for PythonTupleStructSerializer<'py, P> {
     type Ok = Bound<'py, PyAny>;
     type Error = PythonizeError;
 
@@ -543,11 +1028,17 @@ impl<'py, P: PythonizeTypes> ser::SerializeTupleStruct for PythonCollectionSeria
     where
         T: ?Sized + Serialize,
     {
-        ser::SerializeSeq::serialize_element(self, value)
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
     }
 
     fn end(self) -> Result<Bound<'py, PyAny>> {
-        ser::SerializeTuple::end(self)
+        if !self.inner.pythonizer.options.named_tuples {
+            return ser::SerializeTuple::end(self.inner);
+        }
+        let py = self.inner.pythonizer.py;
+        let fields = NamedTupleFields::Positional(self.inner.items.len());
+        let class = namedtuple_class(py, self.name, fields)?;
+        Ok(class.call1(PyTuple::new(py, self.inner.items)?)?)
     }
 }
 
@@ -563,13 +1054,9 @@ impl<'py, P: PythonizeTypes> ser::SerializeTupleVariant for PythonTupleVariantSe
     }
 
     fn end(self) -> Result<Bound<'py, PyAny>> {
-        let mut m = P::NamedMap::builder(self.inner.py, 1, self.name)?;
-        P::NamedMap::push_field(
-            &mut m,
-            PyString::new(self.inner.py, self.variant),
-            ser::SerializeTuple::end(self.inner)?,
-        )?;
-        Ok(P::NamedMap::finish(m)?.into_any())
+        let pythonizer = self.inner.pythonizer;
+        let value = ser::SerializeTuple::end(self.inner)?;
+        pythonizer.serialize_variant(self.name, self.variant, value)
     }
 }
 
@@ -581,7 +1068,7 @@ impl<'py, P: PythonizeTypes> ser::SerializeMap for PythonMapSerializer<'py, P> {
     where
         T: ?Sized + Serialize,
     {
-        self.key = Some(pythonize_custom::<P, _>(self.py, key)?);
+        self.key = Some(self.pythonizer.serialize_map_key(key)?);
         Ok(())
     }
 
@@ -589,13 +1076,8 @@ impl<'py, P: PythonizeTypes> ser::SerializeMap for PythonMapSerializer<'py, P> {
     where
         T: ?Sized + Serialize,
     {
-        P::Map::push_item(
-            &mut self.builder,
-            self.key
-                .take()
-                .expect("serialize_value should always be called after serialize_key"),
-            pythonize_custom::<P, _>(self.py, value)?,
-        )?;
+        let (key, value) = self.pythonizer.serialize_map_entry(&mut self.key, value)?;
+        P::Map::push_item(&mut self.builder, key, value)?;
         Ok(())
     }
 
@@ -604,7 +1086,6 @@ impl<'py, P: PythonizeTypes> ser::SerializeMap for PythonMapSerializer<'py, P> {
     }
 }
 
-#[cfg(feature = "arbitrary_precision")]
 impl<'py, P: PythonizeTypes> ser::SerializeStruct for StructSerializer<'py, P> {
     type Ok = Bound<'py, PyAny>;
     type Error = PythonizeError;
@@ -615,6 +1096,8 @@ impl<'py, P: PythonizeTypes> ser::SerializeStruct for StructSerializer<'py, P> {
     {
         match self {
             StructSerializer::Struct(s) => s.serialize_field(key, value),
+            StructSerializer::NamedTuple(s) => s.serialize_field(key, value),
+            #[cfg(feature = "arbitrary_precision")]
             StructSerializer::Number { number_string, .. } => {
                 let serde_json::Value::String(s) = value
                     .serialize(serde_json::value::Serializer)
@@ -634,10 +1117,11 @@ impl<'py, P: PythonizeTypes> ser::SerializeStruct for StructSerializer<'py, P> {
     fn end(self) -> Result<Bound<'py, PyAny>> {
         match self {
             StructSerializer::Struct(s) => s.end(),
+            StructSerializer::NamedTuple(s) => s.end(),
+            #[cfg(feature = "arbitrary_precision")]
             StructSerializer::Number {
                 py,
                 number_string: Some(s),
-                ..
             } => {
                 if let Ok(i) = s.parse::<i64>() {
                     return Ok(PyInt::new(py, i).into_any());
@@ -655,6 +1139,7 @@ impl<'py, P: PythonizeTypes> ser::SerializeStruct for StructSerializer<'py, P> {
                     .call1((s.as_str(),))
                     .map_err(|e| PythonizeError::msg(format!("Invalid number: {}", e)))
             }
+            #[cfg(feature = "arbitrary_precision")]
             StructSerializer::Number { .. } => Err(PythonizeError::msg("Empty serde_json::Number")),
         }
     }
@@ -668,20 +1153,23 @@ impl<'py, P: PythonizeTypes> ser::SerializeStruct for PythonStructDictSerializer
     where
         T: ?Sized + Serialize,
     {
-        P::NamedMap::push_field(
-            &mut self.builder,
-            PyString::new(self.py, key),
-            pythonize_custom::<P, _>(self.py, value)?,
-        )?;
+        if let Some((key, value)) = self.pythonizer.serialize_struct_field(key, value)? {
+            P::NamedMap::push_field(&mut self.builder, key, value)?;
+        }
         Ok(())
     }
 
     fn end(self) -> Result<Bound<'py, PyAny>> {
-        Ok(P::NamedMap::finish(self.builder)?.into_any())
+        let dict = P::NamedMap::finish(self.builder)?.into_any();
+        if self.pythonizer.options.marked {
+            let py = self.pythonizer.py;
+            return Ok(marker_class(py, &STRUCT, "Struct")?.call1((dict,))?);
+        }
+        Ok(dict)
     }
 }
 
-impl<'py, P: PythonizeTypes> ser::SerializeStructVariant for PythonStructVariantSerializer<'py, P> {
+impl<'py, P: PythonizeTypes> ser::SerializeStruct for PythonNamedTupleSerializer<'py, P> {
     type Ok = Bound<'py, PyAny>;
     type Error = PythonizeError;
 
@@ -689,80 +1177,764 @@ impl<'py, P: PythonizeTypes> ser::SerializeStructVariant for PythonStructVariant
     where
         T: ?Sized + Serialize,
     {
-        P::NamedMap::push_field(
-            &mut self.inner.builder,
-            PyString::new(self.inner.py, key),
-            pythonize_custom::<P, _>(self.inner.py, value)?,
-        )?;
+        self.fields.push(key);
+        self.values.push(value.serialize(self.pythonizer)?);
         Ok(())
     }
 
     fn end(self) -> Result<Bound<'py, PyAny>> {
-        let v = P::NamedMap::finish(self.inner.builder)?;
-        let mut m = P::NamedMap::builder(self.inner.py, 1, self.name)?;
-        P::NamedMap::push_field(
-            &mut m,
-            PyString::new(self.inner.py, self.variant),
-            v.into_any(),
-        )?;
-        Ok(P::NamedMap::finish(m)?.into_any())
+        let py = self.pythonizer.py;
+        let class = namedtuple_class(py, self.name, NamedTupleFields::Named(&self.fields))?;
+        Ok(class.call1(PyTuple::new(py, self.values)?)?)
     }
 }
 
-#[cfg(test)]
-mod test {
-    use super::pythonize;
-    use maplit::hashmap;
-    use pyo3::prelude::*;
-    use pyo3::pybacked::PyBackedStr;
-    use pyo3::types::{PyBytes, PyDict};
-    use serde::Serialize;
+impl<'py, P: PythonizeTypes> ser::SerializeStructVariant for PythonStructVariantSerializer<'py, P> {
+    type Ok = Bound<'py, PyAny>;
+    type Error = PythonizeError;
 
-    fn test_ser<T>(src: T, expected: &str)
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        Python::attach(|py| -> PyResult<()> {
-            let obj = pythonize(py, &src)?;
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
 
-            let locals = PyDict::new(py);
-            locals.set_item("obj", obj)?;
+    fn end(self) -> Result<Bound<'py, PyAny>> {
+        let pythonizer = self.inner.pythonizer;
+        let value = P::NamedMap::finish(self.inner.builder)?.into_any();
+        pythonizer.serialize_variant(self.name, self.variant, value)
+    }
+}
 
-            py.run(
-                c"import json; result = json.dumps(obj, separators=(',', ':'))",
-                None,
-                Some(&locals),
-            )?;
-            let result = locals.get_item("result")?.unwrap();
-            let result = result.extract::<PyBackedStr>()?;
+/// Serializer which only accepts `u8` values, used to detect sequences of bytes
+struct ByteSerializer;
 
-            assert_eq!(result, expected);
-            assert_eq!(serde_json::to_string(&src).unwrap(), expected);
+macro_rules! not_a_byte {
+    ($($method:ident($($arg:ty),*);)*) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<u8> {
+                Err(PythonizeError::msg("not a byte"))
+            }
+        )*
+    };
+}
 
-            Ok(())
-        })
-        .unwrap();
+impl ser::Serializer for ByteSerializer {
+    type Ok = u8;
+    type Error = PythonizeError;
+    type SerializeSeq = ser::Impossible<u8, PythonizeError>;
+    type SerializeTuple = ser::Impossible<u8, PythonizeError>;
+    type SerializeTupleStruct = ser::Impossible<u8, PythonizeError>;
+    type SerializeTupleVariant = ser::Impossible<u8, PythonizeError>;
+    type SerializeMap = ser::Impossible<u8, PythonizeError>;
+    type SerializeStruct = ser::Impossible<u8, PythonizeError>;
+    type SerializeStructVariant = ser::Impossible<u8, PythonizeError>;
+
+    fn serialize_u8(self, v: u8) -> Result<u8> {
+        Ok(v)
+    }
+
+    not_a_byte! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_i128(i128);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_u128(u128);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_char(char);
+        serialize_str(&str);
+        serialize_bytes(&[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(&'static str);
+        serialize_unit_variant(&'static str, u32, &'static str);
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<u8>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(PythonizeError::msg("not a byte"))
     }
 
-    #[test]
-    fn test_empty_struct() {
-        #[derive(Serialize)]
-        struct Empty;
+    fn serialize_newtype_struct<T>(self, _name: &'static str, _value: &T) -> Result<u8>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(PythonizeError::msg("not a byte"))
+    }
 
-        test_ser(Empty, "null");
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<u8>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(PythonizeError::msg("not a byte"))
     }
 
-    #[test]
-    fn test_struct() {
-        #[derive(Serialize)]
-        struct Struct {
-            foo: String,
-            bar: usize,
-        }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(PythonizeError::msg("not a byte"))
+    }
 
-        test_ser(
-            Struct {
-                foo: "foo".to_string(),
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(PythonizeError::msg("not a byte"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(PythonizeError::msg("not a byte"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(PythonizeError::msg("not a byte"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(PythonizeError::msg("not a byte"))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(PythonizeError::msg("not a byte"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(PythonizeError::msg("not a byte"))
+    }
+}
+
+/// Serializer which only checks whether a value is `Option::None`, used by
+/// [`Pythonizer::with_skip_none_fields`] so that units and other values which become Python `None`
+/// are kept
+struct NoneSerializer;
+
+fn not_none() -> PythonizeError {
+    PythonizeError::msg("not None")
+}
+
+macro_rules! not_none {
+    ($($method:ident($($arg:ty),*);)*) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<bool> {
+                Ok(false)
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for NoneSerializer {
+    type Ok = bool;
+    type Error = PythonizeError;
+    type SerializeSeq = ser::Impossible<bool, PythonizeError>;
+    type SerializeTuple = ser::Impossible<bool, PythonizeError>;
+    type SerializeTupleStruct = ser::Impossible<bool, PythonizeError>;
+    type SerializeTupleVariant = ser::Impossible<bool, PythonizeError>;
+    type SerializeMap = ser::Impossible<bool, PythonizeError>;
+    type SerializeStruct = ser::Impossible<bool, PythonizeError>;
+    type SerializeStructVariant = ser::Impossible<bool, PythonizeError>;
+
+    fn serialize_none(self) -> Result<bool> {
+        Ok(true)
+    }
+
+    not_none! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_i128(i128);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_u128(u128);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_char(char);
+        serialize_str(&str);
+        serialize_bytes(&[u8]);
+        serialize_unit();
+        serialize_unit_struct(&'static str);
+        serialize_unit_variant(&'static str, u32, &'static str);
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<bool>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(false)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, _value: &T) -> Result<bool>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(false)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<bool>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(false)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(not_none())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(not_none())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(not_none())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(not_none())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(not_none())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(not_none())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(not_none())
+    }
+}
+
+/// Serializer for mapping keys with [`Pythonizer::with_json_keys`], which converts them to strings
+/// in the same way as `serde_json`
+struct MapKeySerializer;
+
+fn key_must_be_a_string() -> PythonizeError {
+    PythonizeError::msg("key must be a string")
+}
+
+macro_rules! key_to_string {
+    ($($method:ident($ty:ty);)*) => {
+        $(
+            fn $method(self, v: $ty) -> Result<String> {
+                Ok(v.to_string())
+            }
+        )*
+    };
+}
+
+macro_rules! key_not_a_string {
+    ($($method:ident($($arg:ty),*);)*) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<String> {
+                Err(key_must_be_a_string())
+            }
+        )*
+    };
+}
+
+/// A float type which can be formatted by [`json_float_key`]
+trait JsonFloat: fmt::LowerExp {
+    /// The decimal exponents which `serde_json` writes without scientific notation
+    const FIXED_EXP: RangeInclusive<isize>;
+}
+
+impl JsonFloat for f32 {
+    const FIXED_EXP: RangeInclusive<isize> = -6..=12;
+}
+
+impl JsonFloat for f64 {
+    const FIXED_EXP: RangeInclusive<isize> = -5..=15;
+}
+
+/// Format a finite float as `serde_json` does: the shortest representation which round-trips, in
+/// scientific notation only for very large or small magnitudes.
+fn json_float_key<T>(v: T) -> String
+where
+    T: JsonFloat,
+{
+    // `{:e}` also gives the shortest digits, e.g. `-1.5e-7`, so only the layout differs
+    let formatted = format!("{:e}", v);
+    let (mantissa, exp) = formatted.split_once('e').unwrap();
+    let exp: isize = exp.parse().unwrap();
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => ("-", mantissa),
+        None => ("", mantissa),
+    };
+    let digits = mantissa.replace('.', "");
+    // The value is `0.{digits} * 10^point`
+    let point = exp + 1;
+    let len = digits.len() as isize;
+
+    let body = if !T::FIXED_EXP.contains(&exp) {
+        let exp = if exp < 0 {
+            exp.to_string()
+        } else {
+            format!("+{}", exp)
+        };
+        if len == 1 {
+            format!("{}e{}", digits, exp)
+        } else {
+            format!("{}.{}e{}", &digits[..1], &digits[1..], exp)
+        }
+    } else if point <= 0 {
+        format!("0.{}{}", "0".repeat(-point as usize), digits)
+    } else if len <= point {
+        format!("{}{}.0", digits, "0".repeat((point - len) as usize))
+    } else {
+        let (int, frac) = digits.split_at(point as usize);
+        format!("{}.{}", int, frac)
+    };
+    format!("{}{}", sign, body)
+}
+
+impl ser::Serializer for MapKeySerializer {
+    type Ok = String;
+    type Error = PythonizeError;
+    type SerializeSeq = ser::Impossible<String, PythonizeError>;
+    type SerializeTuple = ser::Impossible<String, PythonizeError>;
+    type SerializeTupleStruct = ser::Impossible<String, PythonizeError>;
+    type SerializeTupleVariant = ser::Impossible<String, PythonizeError>;
+    type SerializeMap = ser::Impossible<String, PythonizeError>;
+    type SerializeStruct = ser::Impossible<String, PythonizeError>;
+    type SerializeStructVariant = ser::Impossible<String, PythonizeError>;
+
+    key_to_string! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_i128(i128);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_u128(u128);
+        serialize_char(char);
+        serialize_str(&str);
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<String> {
+        if !v.is_finite() {
+            return Err(PythonizeError::msg(
+                "float key must be finite (got NaN or +/-inf)",
+            ));
+        }
+        Ok(json_float_key(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<String> {
+        if !v.is_finite() {
+            return Err(PythonizeError::msg(
+                "float key must be finite (got NaN or +/-inf)",
+            ));
+        }
+        Ok(json_float_key(v))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    key_not_a_string! {
+        serialize_bytes(&[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(&'static str);
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(key_must_be_a_string())
+    }
+}
+
+/// Serializer for [`pythonize_into_dict`] and [`pythonize_onto`], which writes the entries of a
+/// map or struct into an existing object instead of building a new mapping
+struct EntriesSerializer<'a, 'py, P> {
+    target: EntriesTarget<'a, 'py>,
+    pythonizer: Pythonizer<'py, P>,
+    key: Option<Bound<'py, PyAny>>,
+}
+
+enum EntriesTarget<'a, 'py> {
+    /// Insert entries as items of a `dict`
+    Dict(&'a Bound<'py, PyDict>),
+    /// Set entries as attributes of an object
+    Attributes(&'a Bound<'py, PyAny>),
+}
+
+impl<'py, P> EntriesSerializer<'_, 'py, P> {
+    fn set(&self, key: Bound<'py, PyAny>, value: Bound<'py, PyAny>) -> Result<()> {
+        match self.target {
+            EntriesTarget::Dict(dict) => dict.set_item(key, value)?,
+            EntriesTarget::Attributes(obj) => {
+                let name = key
+                    .cast_into::<PyString>()
+                    .map_err(|_| PythonizeError::msg("attribute names must be strings"))?;
+                if self.pythonizer.options.existing_attributes && !obj.hasattr(&name)? {
+                    return Err(PyAttributeError::new_err(format!(
+                        "'{}' object has no attribute '{}'",
+                        obj.get_type().qualname()?,
+                        name
+                    ))
+                    .into());
+                }
+                obj.setattr(name, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn not_a_map() -> PythonizeError {
+    PythonizeError::msg("expected a map or struct")
+}
+
+macro_rules! not_a_map {
+    ($($method:ident($($arg:ty),*);)*) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<()> {
+                Err(not_a_map())
+            }
+        )*
+    };
+}
+
+impl<'a, 'py, P: PythonizeTypes> ser::Serializer for EntriesSerializer<'a, 'py, P> {
+    type Ok = ();
+    type Error = PythonizeError;
+    type SerializeSeq = ser::Impossible<(), PythonizeError>;
+    type SerializeTuple = ser::Impossible<(), PythonizeError>;
+    type SerializeTupleStruct = ser::Impossible<(), PythonizeError>;
+    type SerializeTupleVariant = ser::Impossible<(), PythonizeError>;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = ser::Impossible<(), PythonizeError>;
+
+    not_a_map! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_i128(i128);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_u128(u128);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_char(char);
+        serialize_str(&str);
+        serialize_bytes(&[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(&'static str);
+        serialize_unit_variant(&'static str, u32, &'static str);
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(not_a_map())
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(not_a_map())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(not_a_map())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(not_a_map())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(not_a_map())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(not_a_map())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(not_a_map())
+    }
+}
+
+impl<P: PythonizeTypes> ser::SerializeMap for EntriesSerializer<'_, '_, P> {
+    type Ok = ();
+    type Error = PythonizeError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.key = Some(self.pythonizer.serialize_map_key(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let (key, value) = self.pythonizer.serialize_map_entry(&mut self.key, value)?;
+        self.set(key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<P: PythonizeTypes> ser::SerializeStruct for EntriesSerializer<'_, '_, P> {
+    type Ok = ();
+    type Error = PythonizeError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        match self.pythonizer.serialize_struct_field(key, value)? {
+            Some((key, value)) => self.set(key.into_any(), value),
+            None => Ok(()),
+        }
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        pythonize, pythonize_extend, pythonize_into_dict, pythonize_onto, PythonizeDefault,
+        Pythonizer,
+    };
+    use crate::FieldCase;
+    use maplit::hashmap;
+    use pyo3::prelude::*;
+    use pyo3::pybacked::PyBackedStr;
+    use pyo3::types::{PyBytes, PyDict, PyList, PyType};
+    use serde::Serialize;
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::fmt;
+
+    fn test_ser<T>(src: T, expected: &str)
+    where
+        T: Serialize,
+    {
+        Python::attach(|py| -> PyResult<()> {
+            let obj = pythonize(py, &src)?;
+
+            let locals = PyDict::new(py);
+            locals.set_item("obj", obj)?;
+
+            py.run(
+                c"import json; result = json.dumps(obj, separators=(',', ':'))",
+                None,
+                Some(&locals),
+            )?;
+            let result = locals.get_item("result")?.unwrap();
+            let result = result.extract::<PyBackedStr>()?;
+
+            assert_eq!(result, expected);
+            assert_eq!(serde_json::to_string(&src).unwrap(), expected);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_empty_struct() {
+        #[derive(Serialize)]
+        struct Empty;
+
+        test_ser(Empty, "null");
+    }
+
+    #[test]
+    fn test_struct() {
+        #[derive(Serialize)]
+        struct Struct {
+            foo: String,
+            bar: usize,
+        }
+
+        test_ser(
+            Struct {
+                foo: "foo".to_string(),
                 bar: 5,
             },
             r#"{"foo":"foo","bar":5}"#,
@@ -931,6 +2103,7 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::needless_borrows_for_generic_args)]
     fn test_bytes() {
         // serde treats &[u8] as a sequence of integers due to lack of specialization
         test_ser(b"foo", "[102,111,111]");
@@ -942,4 +2115,674 @@ mod test {
                 .expect("bytes will always compare successfully"));
         });
     }
+
+    #[test]
+    fn test_as_set() {
+        #[derive(Serialize)]
+        struct Tags {
+            #[serde(with = "crate::as_::set")]
+            names: BTreeSet<&'static str>,
+            #[serde(with = "crate::as_::frozenset")]
+            ids: Vec<u32>,
+        }
+
+        let tags = Tags {
+            names: BTreeSet::from(["a"]),
+            ids: vec![1, 1],
+        };
+
+        Python::attach(|py| {
+            let obj = pythonize(py, &tags).unwrap();
+            assert_eq!(
+                obj.repr().unwrap().to_string(),
+                "{'names': {'a'}, 'ids': frozenset({1})}"
+            );
+        });
+
+        // Other serializers are unaffected
+        assert_eq!(
+            serde_json::to_value(&tags).unwrap(),
+            serde_json::json!({"names": ["a"], "ids": [1, 1]})
+        );
+    }
+
+    #[test]
+    fn test_as_tuple_bytes_decimal() {
+        #[derive(Serialize)]
+        struct Reading {
+            #[serde(with = "crate::as_::tuple")]
+            position: Vec<i32>,
+            #[serde(with = "crate::as_::bytes")]
+            raw: [u8; 2],
+            #[serde(with = "crate::as_::decimal")]
+            price: &'static str,
+            #[serde(with = "crate::as_::decimal")]
+            ratio: f64,
+        }
+
+        let reading = Reading {
+            position: vec![1, 2],
+            raw: [0, 255],
+            price: "1.10",
+            ratio: 0.1,
+        };
+
+        Python::attach(|py| {
+            let obj = pythonize(py, &reading).unwrap();
+            assert_eq!(
+                obj.repr().unwrap().to_string(),
+                "{'position': (1, 2), 'raw': b'\\x00\\xff', 'price': Decimal('1.10'), 'ratio': Decimal('0.1')}"
+            );
+
+            // Values which are already bytes are kept as they are
+            #[derive(Serialize)]
+            struct Raw(#[serde(with = "crate::as_::bytes")] serde_bytes::ByteBuf);
+            let obj = pythonize(py, &Raw(serde_bytes::ByteBuf::from(vec![1]))).unwrap();
+            assert!(obj.is_instance_of::<PyBytes>());
+
+            #[derive(Serialize)]
+            struct NotBytes(#[serde(with = "crate::as_::bytes")] Vec<i32>);
+            assert!(pythonize(py, &NotBytes(vec![256])).is_err());
+        });
+
+        // Other serializers are unaffected
+        assert_eq!(
+            serde_json::to_value(&reading).unwrap(),
+            serde_json::json!({"position": [1, 2], "raw": [0, 255], "price": "1.10", "ratio": 0.1})
+        );
+    }
+
+    #[test]
+    fn test_json_keys() {
+        #[derive(Serialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        enum Kind {
+            A,
+        }
+
+        #[derive(Serialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        struct Id(u32);
+
+        Python::attach(|py| {
+            let pythonizer = Pythonizer::new(py).with_json_keys(true);
+
+            let obj = BTreeMap::from([(1u32, 'a'), (2, 'b')])
+                .serialize(pythonizer)
+                .unwrap();
+            assert_eq!(obj.repr().unwrap().to_string(), "{'1': 'a', '2': 'b'}");
+
+            let obj = BTreeMap::from([(Kind::A, 1)])
+                .serialize(pythonizer)
+                .unwrap();
+            assert_eq!(obj.repr().unwrap().to_string(), "{'A': 1}");
+            let obj = BTreeMap::from([(Id(7), 1)]).serialize(pythonizer).unwrap();
+            assert_eq!(obj.repr().unwrap().to_string(), "{'7': 1}");
+            let obj = BTreeMap::from([(true, 1)]).serialize(pythonizer).unwrap();
+            assert_eq!(obj.repr().unwrap().to_string(), "{'true': 1}");
+
+            // Struct fields are unaffected
+            let obj = hashmap! {"a" => hashmap! {1 => 2}}
+                .serialize(pythonizer)
+                .unwrap();
+            assert_eq!(obj.repr().unwrap().to_string(), "{'a': {'1': 2}}");
+
+            // Keys which serde_json rejects
+            let err = BTreeMap::from([((1, 2), 'a')])
+                .serialize(pythonizer)
+                .unwrap_err();
+            assert_eq!(err.to_string(), "key must be a string");
+            assert!(hashmap! {Some(1) => 1}.serialize(pythonizer).is_err());
+
+            // Native keys by default
+            let obj = pythonize(py, &BTreeMap::from([((1, 2), 'a')])).unwrap();
+            assert_eq!(obj.repr().unwrap().to_string(), "{(1, 2): 'a'}");
+        });
+    }
+
+    #[test]
+    fn test_json_float_keys() {
+        let floats = [
+            0.0,
+            -0.0,
+            1.0,
+            -1.5,
+            0.1,
+            1e-5,
+            1e-6,
+            1.5e-7,
+            123456789.125,
+            1e15,
+            1e16,
+            1.25e16,
+            1e100,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+            5e-324,
+        ];
+        // `f32` switches to scientific notation at different magnitudes
+        let floats32 = [
+            0.1f32,
+            1e-5,
+            1e-6,
+            1e-7,
+            1.5e-7,
+            16777216.0,
+            1e12,
+            1e13,
+            1.5e14,
+            f32::MAX,
+            f32::MIN_POSITIVE,
+        ];
+
+        fn json_float_key<T>(
+            pythonizer: Pythonizer<'_, PythonizeDefault>,
+            float: T,
+        ) -> (String, String)
+        where
+            T: Serialize + PartialEq + fmt::Display + Copy,
+        {
+            let obj = hashmap! {OrderedFloat(float) => 0}
+                .serialize(pythonizer)
+                .unwrap();
+            let key: String = obj
+                .call_method0("popitem")
+                .unwrap()
+                .get_item(0)
+                .unwrap()
+                .extract()
+                .unwrap();
+            let json = serde_json::to_value(hashmap! {OrderedFloat(float) => 0}).unwrap();
+            (
+                json.as_object().unwrap().keys().next().unwrap().clone(),
+                key,
+            )
+        }
+
+        Python::attach(|py| {
+            let pythonizer = Pythonizer::new(py).with_json_keys(true);
+            for float in floats {
+                let (expected, key) = json_float_key(pythonizer, float);
+                assert_eq!(expected, key);
+            }
+            for float in floats32 {
+                let (expected, key) = json_float_key(pythonizer, float);
+                assert_eq!(expected, key);
+            }
+            assert_eq!(json_float_key(pythonizer, 1e13f32).1, "1e+13");
+            assert_eq!(json_float_key(pythonizer, 1.5e14f32).1, "1.5e+14");
+            assert_eq!(json_float_key(pythonizer, 1e-6f32).1, "0.000001");
+            assert_eq!(json_float_key(pythonizer, 1e-6f64).1, "1e-6");
+
+            let err = hashmap! {OrderedFloat(f64::NAN) => 0}
+                .serialize(pythonizer)
+                .unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "float key must be finite (got NaN or +/-inf)"
+            );
+        });
+    }
+
+    /// A float which can be used as a key, for testing
+    #[derive(Serialize, PartialEq)]
+    struct OrderedFloat<T>(T);
+
+    impl<T: PartialEq> Eq for OrderedFloat<T> {}
+
+    impl<T: fmt::Display> std::hash::Hash for OrderedFloat<T> {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.0.to_string().hash(state)
+        }
+    }
+
+    #[test]
+    fn test_pythonize_iter() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        Python::attach(|py| {
+            let produced = Arc::new(AtomicUsize::new(0));
+            let counter = produced.clone();
+            let items = (0..).map(move |i| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                (i % 2 == 1).then_some(i)
+            });
+
+            let iter = super::pythonize_iter(py, items).unwrap();
+            assert_eq!(produced.load(Ordering::SeqCst), 0);
+
+            // `None` items don't end the iteration
+            let first: Vec<Option<i32>> = (0..4)
+                .map(|_| iter.call_method0("__next__").unwrap().extract().unwrap())
+                .collect();
+            assert_eq!(first, vec![None, Some(1), None, Some(3)]);
+            assert_eq!(produced.load(Ordering::SeqCst), 4);
+
+            let iter = Pythonizer::new(py)
+                .with_byte_sequences(true)
+                .serialize_iter(vec![vec![1u8, 2], vec![]])
+                .unwrap();
+            let items = py.get_type::<pyo3::types::PyList>().call1((iter,)).unwrap();
+            assert_eq!(items.repr().unwrap().to_string(), "[b'\\x01\\x02', []]");
+        });
+    }
+
+    #[test]
+    fn test_pythonize_iter_error() {
+        #[derive(Serialize, PartialEq, Eq, Hash)]
+        enum Key {
+            Pair(i32, i32),
+            Name,
+        }
+
+        Python::attach(|py| {
+            let items = vec![hashmap! {Key::Pair(1, 2) => 1}, hashmap! {Key::Name => 3}];
+            let iter = Pythonizer::new(py)
+                .with_json_keys(true)
+                .serialize_iter(items)
+                .unwrap();
+            let err = iter.call_method0("__next__").unwrap_err();
+            assert_eq!(err.to_string(), "Exception: key must be a string");
+            // Later items can still be read
+            let item = iter.call_method0("__next__").unwrap();
+            assert_eq!(item.repr().unwrap().to_string(), "{'Name': 3}");
+            assert!(iter
+                .call_method0("__next__")
+                .unwrap_err()
+                .is_instance_of::<pyo3::exceptions::PyStopIteration>(py));
+        });
+    }
+
+    #[test]
+    fn test_pythonize_into_dict() {
+        #[derive(Serialize)]
+        struct Options {
+            verbose: bool,
+            level: u8,
+        }
+
+        #[derive(Serialize)]
+        struct Wrapper(Options);
+
+        Python::attach(|py| {
+            let kwargs = py.eval(c"{'name': 'x', 'level': 0}", None, None).unwrap();
+            let kwargs = kwargs.cast::<PyDict>().unwrap();
+            let options = Options {
+                verbose: true,
+                level: 2,
+            };
+            pythonize_into_dict(kwargs, &options).unwrap();
+            pythonize_into_dict(kwargs, &hashmap! {"extra" => vec![1]}).unwrap();
+            assert_eq!(
+                kwargs.repr().unwrap().to_string(),
+                "{'name': 'x', 'level': 2, 'verbose': True, 'extra': [1]}"
+            );
+
+            let dict = PyDict::new(py);
+            Pythonizer::new(py)
+                .with_json_keys(true)
+                .serialize_into_dict(&dict, &Wrapper(options))
+                .unwrap();
+            Pythonizer::new(py)
+                .with_json_keys(true)
+                .serialize_into_dict(&dict, &hashmap! {1 => 2})
+                .unwrap();
+            assert_eq!(
+                dict.repr().unwrap().to_string(),
+                "{'verbose': True, 'level': 2, '1': 2}"
+            );
+
+            let err = pythonize_into_dict(&dict, &vec![1]).unwrap_err();
+            assert_eq!(err.to_string(), "expected a map or struct");
+        });
+    }
+
+    #[test]
+    fn test_pythonize_onto() {
+        #[derive(Serialize)]
+        struct Update {
+            x: i32,
+            #[serde(flatten)]
+            extra: BTreeMap<String, String>,
+        }
+
+        Python::attach(|py| {
+            let locals = PyDict::new(py);
+            py.run(
+                c"import dataclasses\n@dataclasses.dataclass\nclass Point:\n    x: int\n    label: str = ''",
+                None,
+                Some(&locals),
+            )
+            .unwrap();
+            let point = py.eval(c"Point(1)", None, Some(&locals)).unwrap();
+
+            let update = Update {
+                x: 5,
+                extra: [("label".to_string(), "a".to_string())].into(),
+            };
+            pythonize_onto(&point, &update).unwrap();
+            assert_eq!(point.repr().unwrap().to_string(), "Point(x=5, label='a')");
+
+            let update = Update {
+                x: 6,
+                extra: [("colour".to_string(), "red".to_string())].into(),
+            };
+            let err = Pythonizer::new(py)
+                .with_existing_attributes(true)
+                .serialize_onto(&point, &update)
+                .unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "AttributeError: 'Point' object has no attribute 'colour'"
+            );
+            // Fields before the missing attribute were already set
+            assert_eq!(point.getattr("x").unwrap().extract::<i32>().unwrap(), 6);
+
+            pythonize_onto(&point, &update).unwrap();
+            assert_eq!(point.getattr("colour").unwrap().to_string(), "red");
+
+            let err = pythonize_onto(&point, &hashmap! {1 => 2}).unwrap_err();
+            assert_eq!(err.to_string(), "attribute names must be strings");
+        });
+    }
+
+    #[test]
+    fn test_field_case() {
+        #[derive(Serialize)]
+        struct Request {
+            user_id: u32,
+            retry_policy: Policy,
+            extra_tags: BTreeMap<&'static str, u8>,
+        }
+
+        #[derive(Serialize)]
+        enum Policy {
+            Backoff { max_delay: u32 },
+        }
+
+        Python::attach(|py| {
+            let request = Request {
+                user_id: 1,
+                retry_policy: Policy::Backoff { max_delay: 5 },
+                extra_tags: [("some_tag", 1)].into(),
+            };
+            let obj = request
+                .serialize(Pythonizer::new(py).with_field_case(FieldCase::CamelCase))
+                .unwrap();
+            // Map keys and variant names are kept as they are
+            assert_eq!(
+                obj.repr().unwrap().to_string(),
+                "{'userId': 1, 'retryPolicy': {'Backoff': {'maxDelay': 5}}, 'extraTags': {'some_tag': 1}}"
+            );
+
+            let dict = PyDict::new(py);
+            Pythonizer::new(py)
+                .with_field_case(FieldCase::KebabCase)
+                .serialize_into_dict(&dict, &request)
+                .unwrap();
+            assert!(dict.contains("retry-policy").unwrap());
+
+            #[derive(Serialize)]
+            struct Flattened {
+                user_id: u32,
+                #[serde(flatten)]
+                extra: Extra,
+            }
+
+            #[derive(Serialize)]
+            struct Extra {
+                max_delay: u32,
+            }
+
+            // Flattened fields are written as map entries, which are not converted
+            let obj = Flattened {
+                user_id: 1,
+                extra: Extra { max_delay: 5 },
+            }
+            .serialize(Pythonizer::new(py).with_field_case(FieldCase::CamelCase))
+            .unwrap();
+            assert_eq!(
+                obj.repr().unwrap().to_string(),
+                "{'user_id': 1, 'max_delay': 5}"
+            );
+        });
+    }
+
+    #[test]
+    fn test_skip_none_fields() {
+        #[derive(Serialize)]
+        struct Marker;
+
+        #[derive(Serialize)]
+        struct Query {
+            text: String,
+            limit: Option<u32>,
+            offset: Option<u32>,
+            filters: Vec<Option<u32>>,
+            extra: BTreeMap<&'static str, Option<u32>>,
+            marker: Marker,
+            unit: (),
+        }
+
+        #[derive(Serialize)]
+        enum Request {
+            Search { query: Query, page: Option<u32> },
+        }
+
+        let query = Query {
+            text: "x".to_string(),
+            limit: Some(10),
+            offset: None,
+            filters: vec![None],
+            extra: [("a", None)].into(),
+            marker: Marker,
+            unit: (),
+        };
+        Python::attach(|py| {
+            let request = Request::Search { query, page: None };
+            let obj = request
+                .serialize(Pythonizer::new(py).with_skip_none_fields(true))
+                .unwrap();
+            // Only struct fields holding `Option::None` are left out, not units or items of
+            // sequences and maps
+            assert_eq!(
+                obj.repr().unwrap().to_string(),
+                "{'Search': {'query': {'text': 'x', 'limit': 10, 'filters': [None], 'extra': {'a': None}, 'marker': None, 'unit': None}}}"
+            );
+
+            let Request::Search { query, .. } = request;
+            let dict = PyDict::new(py);
+            Pythonizer::new(py)
+                .with_skip_none_fields(true)
+                .serialize_into_dict(&dict, &query)
+                .unwrap();
+            assert!(!dict.contains("offset").unwrap());
+        });
+    }
+
+    #[test]
+    fn test_pythonize_extend() {
+        Python::attach(|py| {
+            let list = PyList::new(py, ["a"]).unwrap();
+            let pairs = vec![(1, 2)];
+            pythonize_extend(&list, &pairs).unwrap();
+            pythonize_extend(&list, [Some(true), None]).unwrap();
+            assert_eq!(
+                list.repr().unwrap().to_string(),
+                "['a', (1, 2), True, None]"
+            );
+
+            Pythonizer::new(py)
+                .with_byte_sequences(true)
+                .serialize_extend(&list, [vec![1u8]])
+                .unwrap();
+            assert_eq!(
+                list.get_item(4).unwrap().repr().unwrap().to_string(),
+                "b'\\x01'"
+            );
+        });
+    }
+
+    #[test]
+    fn test_rejected_object_is_not_left_behind() {
+        Python::attach(|py| {
+            let wrapper = crate::PyObjectWrapper(py.None());
+            assert!(wrapper.serialize(super::ByteSerializer).is_err());
+            assert!(crate::object::take_serialized().is_none());
+        });
+    }
+
+    #[test]
+    fn test_byte_sequences() {
+        #[derive(Serialize)]
+        struct Blobs {
+            data: Vec<u8>,
+            empty: Vec<u8>,
+            wide: Vec<u16>,
+            nested: Vec<Vec<u8>>,
+            mixed: Vec<serde_json::Value>,
+            hash: [u8; 32],
+            pair: (u8, char),
+            rgb: Rgb,
+        }
+
+        #[derive(Serialize)]
+        struct Rgb(u8, u8, u8);
+
+        let blobs = Blobs {
+            data: vec![1, 2, 3],
+            empty: vec![],
+            wide: vec![1, 2],
+            nested: vec![vec![4], vec![5, 6]],
+            mixed: vec![serde_json::json!(1), serde_json::json!("a")],
+            hash: [7; 32],
+            pair: (8, 'a'),
+            rgb: Rgb(1, 2, 3),
+        };
+
+        Python::attach(|py| {
+            let obj = blobs
+                .serialize(Pythonizer::new(py).with_byte_sequences(true))
+                .unwrap();
+            assert_eq!(
+                obj.repr().unwrap().to_string(),
+                format!(
+                    "{{'data': b'\\x01\\x02\\x03', 'empty': [], 'wide': [1, 2], \
+                     'nested': [b'\\x04', b'\\x05\\x06'], 'mixed': [1, 'a'], 'hash': b'{}', \
+                     'pair': (8, 'a'), 'rgb': (1, 2, 3)}}",
+                    "\\x07".repeat(32)
+                )
+            );
+
+            // Arrays are read back from bytes
+            let hash: [u8; 32] = crate::depythonize(&obj.get_item("hash").unwrap()).unwrap();
+            assert_eq!(hash, blobs.hash);
+
+            // Disabled by default
+            let obj = pythonize(py, &blobs.data).unwrap();
+            assert_eq!(obj.repr().unwrap().to_string(), "[1, 2, 3]");
+        });
+    }
+
+    #[test]
+    fn test_named_tuples() {
+        #[derive(Serialize)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        #[derive(Serialize)]
+        struct Pair(i32, String);
+
+        #[derive(Serialize)]
+        struct Kw {
+            r#type: i32,
+            _private: i32,
+        }
+
+        mod other {
+            #[derive(serde::Serialize)]
+            pub struct Point {
+                pub z: i32,
+            }
+        }
+
+        Python::attach(|py| {
+            let pythonizer = Pythonizer::new(py).with_named_tuples(true);
+
+            let obj = Point { x: 1, y: 2 }.serialize(pythonizer).unwrap();
+            assert_eq!(obj.repr().unwrap().to_string(), "Point(x=1, y=2)");
+            assert!(obj.get_type().is(Point { x: 3, y: 4 }
+                .serialize(pythonizer)
+                .unwrap()
+                .get_type()));
+
+            let obj = Pair(1, "a".to_string()).serialize(pythonizer).unwrap();
+            assert_eq!(obj.repr().unwrap().to_string(), "Pair(_0=1, _1='a')");
+
+            // Field names are never renamed, since they could not be read back
+            let err = Kw {
+                r#type: 1,
+                _private: 2,
+            }
+            .serialize(pythonizer)
+            .unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "ValueError: Field names cannot start with an underscore: '_private'"
+            );
+
+            // Types with the same name but different fields have their own class
+            let obj = other::Point { z: 1 }.serialize(pythonizer).unwrap();
+            assert_eq!(obj.repr().unwrap().to_string(), "Point(z=1)");
+        });
+    }
+
+    #[test]
+    fn test_registered_named_tuple() {
+        #[derive(Serialize)]
+        struct Registered {
+            a: u8,
+            b: String,
+        }
+
+        Python::attach(|py| {
+            let locals = PyDict::new(py);
+            py.run(
+                c"\
+from typing import NamedTuple
+
+class Custom(NamedTuple):
+    a: int
+    b: str",
+                None,
+                Some(&locals),
+            )
+            .unwrap();
+            let class = locals.get_item("Custom").unwrap().unwrap();
+            crate::register_namedtuple(py, "Registered", class.cast::<PyType>().unwrap()).unwrap();
+
+            let obj = Registered {
+                a: 1,
+                b: "b".to_string(),
+            }
+            .serialize(Pythonizer::new(py).with_named_tuples(true))
+            .unwrap();
+            assert!(obj.get_type().is(&class));
+            assert_eq!(obj.repr().unwrap().to_string(), "Custom(a=1, b='b')");
+
+            // The class is only used for the same fields
+            mod other {
+                #[derive(serde::Serialize)]
+                pub struct Registered {
+                    pub c: u8,
+                }
+            }
+            let obj = other::Registered { c: 1 }
+                .serialize(Pythonizer::new(py).with_named_tuples(true))
+                .unwrap();
+            assert!(!obj.get_type().is(&class));
+            assert_eq!(obj.repr().unwrap().to_string(), "Registered(c=1)");
+        });
+    }
 }
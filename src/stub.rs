@@ -0,0 +1,398 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use serde::Deserialize;
+
+use crate::error::Result;
+use crate::reflect::{self, Container, Field, Format, Registry, VariantFormat};
+
+/// Generate the source of a Python type stub (`.pyi`) describing the objects which
+/// [`pythonize`](crate::pythonize) produces for `T`, and which [`depythonize`](crate::depythonize)
+/// accepts.
+///
+/// Structs become `TypedDict` classes and enums become a `Union` of their externally tagged
+/// variants: unit variants are `Literal` strings, and every other variant is a single-key
+/// `TypedDict` named after the enum and the variant.
+///
+/// The shape is discovered by tracing `T`'s `Deserialize` implementation, so serde attributes
+/// such as `rename` are taken into account. This has some limits:
+///
+/// - Only the `Deserialize` side is seen, so `skip_serializing_if` and renames which differ
+///   between serializing and deserializing are not reflected.
+/// - The options of [`Pythonizer`](crate::Pythonizer) and
+///   [`Depythonizer`](crate::Depythonizer) (e.g. `with_named_tuples`, `with_field_case` or
+///   `with_skip_none_fields`) are not known, and the default representation is described.
+/// - Structs and enums are identified by their name without the module path, so different
+///   types with the same name are merged into one definition.
+/// - Types which use `deserialize_any` are described as `Any` if they accept `None`, such as
+///   `serde_json::Value`. Untagged and internally tagged enums do not, and cannot be traced.
+pub fn typing_stub<'de, T>() -> Result<String>
+where
+    T: Deserialize<'de>,
+{
+    let mut registry = Registry::default();
+    reflect::trace::<T>(&mut registry)?;
+    Ok(StubWriter::default().write(&registry))
+}
+
+#[derive(Default)]
+struct StubWriter {
+    /// `(module, name)` pairs to import
+    imports: BTreeSet<(&'static str, &'static str)>,
+    definitions: Vec<String>,
+}
+
+impl StubWriter {
+    fn write(mut self, registry: &Registry) -> String {
+        for (name, container) in registry.containers() {
+            match container {
+                Container::Struct(fields) => {
+                    let fields = self.fields(fields);
+                    self.typed_dict(name, &fields);
+                }
+                Container::Enum(variants) => self.union(name, variants),
+            }
+        }
+
+        let mut out = String::new();
+        if !self.imports.is_empty() {
+            let mut modules: BTreeMap<_, Vec<_>> = BTreeMap::new();
+            for (module, name) in self.imports {
+                modules.entry(module).or_default().push(name);
+            }
+            for (module, names) in modules {
+                writeln!(out, "from {} import {}", module, names.join(", ")).unwrap();
+            }
+            out.push('\n');
+        }
+        out.push_str(&self.definitions.join("\n"));
+        out
+    }
+
+    fn fields(&mut self, fields: &[Field]) -> Vec<(&'static str, String)> {
+        fields
+            .iter()
+            .map(|field| (field.name, self.type_of(&field.format)))
+            .collect()
+    }
+
+    fn typed_dict(&mut self, name: &str, fields: &[(&str, String)]) {
+        self.imports.insert(("typing", "TypedDict"));
+        let mut out = String::new();
+        if fields.iter().all(|(field, _)| is_identifier(field)) {
+            writeln!(out, "class {}(TypedDict):", name).unwrap();
+            for (field, ty) in fields {
+                writeln!(out, "    {}: {}", field, ty).unwrap();
+            }
+            if fields.is_empty() {
+                out.push_str("    pass\n");
+            }
+        } else {
+            let fields: Vec<_> = fields
+                .iter()
+                .map(|(field, ty)| format!("{}: {}", quote(field), ty))
+                .collect();
+            writeln!(
+                out,
+                "{} = TypedDict({}, {{{}}})",
+                name,
+                quote(name),
+                fields.join(", ")
+            )
+            .unwrap();
+        }
+        self.definitions.push(out);
+    }
+
+    fn union(&mut self, name: &str, variants: &[(&str, Option<VariantFormat>)]) {
+        let mut units = Vec::new();
+        let mut members = Vec::new();
+        for (variant, format) in variants {
+            let class = format!("{}{}", name, variant);
+            let ty = match format {
+                None | Some(VariantFormat::Unit) => {
+                    units.push(quote(variant));
+                    continue;
+                }
+                Some(VariantFormat::NewType(format)) => self.type_of(format),
+                Some(VariantFormat::Tuple(formats)) => {
+                    self.type_of(&Format::Tuple(formats.clone()))
+                }
+                Some(VariantFormat::Struct(fields)) => {
+                    let fields_class = format!("{}Fields", class);
+                    let fields = self.fields(fields);
+                    self.typed_dict(&fields_class, &fields);
+                    fields_class
+                }
+            };
+            self.typed_dict(&class, &[(variant, ty)]);
+            members.push(class);
+        }
+
+        if !units.is_empty() {
+            self.imports.insert(("typing", "Literal"));
+            members.insert(0, format!("Literal[{}]", units.join(", ")));
+        }
+        let definition = if members.len() == 1 {
+            members.pop().unwrap()
+        } else {
+            self.imports.insert(("typing", "Union"));
+            format!("Union[{}]", members.join(", "))
+        };
+        self.definitions
+            .push(format!("{} = {}\n", name, definition));
+    }
+
+    fn type_of(&mut self, format: &Format) -> String {
+        match format {
+            Format::Any => {
+                self.imports.insert(("typing", "Any"));
+                "Any".into()
+            }
+            Format::Unit => "None".into(),
+            Format::Bool => "bool".into(),
+            Format::Int => "int".into(),
+            Format::Float => "float".into(),
+            Format::Str => "str".into(),
+            Format::Bytes => "bytes".into(),
+            Format::Decimal => {
+                self.imports.insert(("decimal", "Decimal"));
+                "Decimal".into()
+            }
+            Format::Option(inner) => {
+                self.imports.insert(("typing", "Optional"));
+                format!("Optional[{}]", self.type_of(inner))
+            }
+            Format::Seq(inner) => {
+                self.imports.insert(("typing", "List"));
+                format!("List[{}]", self.type_of(inner))
+            }
+            Format::VarTuple(inner) => {
+                self.imports.insert(("typing", "Tuple"));
+                format!("Tuple[{}, ...]", self.type_of(inner))
+            }
+            Format::Set(inner) => {
+                self.imports.insert(("typing", "Set"));
+                format!("Set[{}]", self.type_of(inner))
+            }
+            Format::FrozenSet(inner) => {
+                self.imports.insert(("typing", "FrozenSet"));
+                format!("FrozenSet[{}]", self.type_of(inner))
+            }
+            Format::Tuple(elements) => {
+                self.imports.insert(("typing", "Tuple"));
+                if elements.is_empty() {
+                    return "Tuple[()]".into();
+                }
+                let elements: Vec<_> = elements.iter().map(|e| self.type_of(e)).collect();
+                format!("Tuple[{}]", elements.join(", "))
+            }
+            Format::Map(key, value) => {
+                self.imports.insert(("typing", "Dict"));
+                format!("Dict[{}, {}]", self.type_of(key), self.type_of(value))
+            }
+            Format::Named(name) => name.to_string(),
+        }
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// Whether `name` can be used as an attribute in a class body
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c == '_' || c.is_ascii_alphabetic())
+        && chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+        && !KEYWORDS.contains(&name)
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod test {
+    use super::typing_stub;
+    use pyo3::prelude::*;
+    use pyo3::types::PyDict;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+    use std::ffi::CString;
+
+    #[test]
+    fn test_struct() {
+        #[allow(dead_code)]
+        #[derive(Deserialize)]
+        struct Point {
+            x: i32,
+            y: f64,
+            label: Option<String>,
+        }
+
+        assert_eq!(
+            typing_stub::<Point>().unwrap(),
+            "\
+from typing import Optional, TypedDict
+
+class Point(TypedDict):
+    x: int
+    y: float
+    label: Optional[str]
+"
+        );
+    }
+
+    #[test]
+    fn test_nested() {
+        #[allow(dead_code)]
+        #[derive(Deserialize)]
+        struct Outer {
+            #[serde(rename = "inner-values")]
+            inner: Vec<Inner>,
+            lookup: HashMap<String, (u8, bool)>,
+            #[serde(with = "crate::as_::set")]
+            tags: Vec<String>,
+            #[serde(with = "serde_bytes")]
+            blob: Vec<u8>,
+            #[serde(with = "crate::as_::tuple")]
+            point: Vec<f64>,
+            #[serde(with = "crate::as_::decimal")]
+            price: String,
+            other: serde_json::Value,
+        }
+
+        #[allow(dead_code)]
+        #[derive(Deserialize)]
+        struct Inner {
+            class: char,
+        }
+
+        assert_eq!(
+            typing_stub::<Outer>().unwrap(),
+            "\
+from decimal import Decimal
+from typing import Any, Dict, List, Set, Tuple, TypedDict
+
+Inner = TypedDict(\"Inner\", {\"class\": str})
+
+Outer = TypedDict(\"Outer\", {\"inner-values\": List[Inner], \"lookup\": Dict[str, Tuple[int, bool]], \"tags\": Set[str], \"blob\": bytes, \"point\": Tuple[float, ...], \"price\": Decimal, \"other\": Any})
+"
+        );
+    }
+
+    #[test]
+    fn test_enum() {
+        #[allow(dead_code)]
+        #[derive(Deserialize)]
+        enum Shape {
+            Empty,
+            Circle(f64),
+            Rect(f64, f64),
+            Polygon { points: Vec<(f64, f64)> },
+            Unknown,
+        }
+
+        assert_eq!(
+            typing_stub::<Shape>().unwrap(),
+            "\
+from typing import List, Literal, Tuple, TypedDict, Union
+
+class ShapeCircle(TypedDict):
+    Circle: float
+
+class ShapeRect(TypedDict):
+    Rect: Tuple[float, float]
+
+class ShapePolygonFields(TypedDict):
+    points: List[Tuple[float, float]]
+
+class ShapePolygon(TypedDict):
+    Polygon: ShapePolygonFields
+
+Shape = Union[Literal[\"Empty\", \"Unknown\"], ShapeCircle, ShapeRect, ShapePolygon]
+"
+        );
+    }
+
+    #[test]
+    fn test_untagged_enum() {
+        #[allow(dead_code)]
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            Int(i64),
+            Str(String),
+        }
+
+        #[allow(dead_code)]
+        #[derive(Deserialize)]
+        struct Config {
+            value: Value,
+        }
+
+        assert_eq!(
+            typing_stub::<Config>().unwrap_err().to_string(),
+            "unable to trace `value`, which can only be deserialized with `deserialize_any` \
+             (e.g. an untagged or internally tagged enum): data did not match any variant of \
+             untagged enum Value"
+        );
+        assert!(typing_stub::<Value>()
+            .unwrap_err()
+            .to_string()
+            .starts_with("unable to trace the root,"));
+    }
+
+    #[test]
+    fn test_recursive() {
+        #[allow(dead_code)]
+        #[derive(Deserialize)]
+        struct Tree {
+            children: Vec<Tree>,
+            parent: Option<Box<Tree>>,
+            leaf: Leaf,
+        }
+
+        #[allow(dead_code)]
+        #[derive(Deserialize)]
+        enum Leaf {
+            Branch(Box<Tree>),
+            Node(Box<Leaf>, Box<Leaf>),
+            Empty,
+        }
+
+        let stub = typing_stub::<Tree>().unwrap();
+        assert_eq!(
+            stub,
+            "\
+from typing import List, Literal, Optional, Tuple, TypedDict, Union
+
+class LeafBranch(TypedDict):
+    Branch: Tree
+
+class LeafNode(TypedDict):
+    Node: Tuple[Leaf, Leaf]
+
+Leaf = Union[Literal[\"Empty\"], LeafBranch, LeafNode]
+
+class Tree(TypedDict):
+    children: List[Tree]
+    parent: Optional[Tree]
+    leaf: Leaf
+"
+        );
+
+        // The stub is valid Python, once forward references are allowed
+        Python::attach(|py| {
+            let code =
+                CString::new(format!("from __future__ import annotations\n{}", stub)).unwrap();
+            py.run(&code, None, Some(&PyDict::new(py))).unwrap();
+        });
+    }
+}
@@ -0,0 +1,420 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use serde::de::{self, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::as_;
+use crate::error::PythonizeError;
+
+pub(crate) const VALUE_TOKEN: &str = "$pythonize::private::Value";
+pub(crate) const INT_TOKEN: &str = "$pythonize::private::Int";
+
+/// Any Python value made of builtin types, which (unlike `serde_json::Value`) keeps bytes, tuples,
+/// sets, non-string `dict` keys and integers of any size when converted with
+/// [`pythonize`](crate::pythonize) and [`depythonize`](crate::depythonize).
+///
+/// ```
+/// use pyo3::prelude::*;
+/// use pythonize::{depythonize, pythonize, Value};
+///
+/// Python::attach(|py| {
+///     let obj = py.eval(c"{(1, 2): {b'x'}, 'big': 2 ** 100}", None, None).unwrap();
+///     let value: Value = depythonize(&obj).unwrap();
+///     let Value::Dict(entries) = &value else { panic!() };
+///     assert_eq!(entries[0].0, Value::Tuple(vec![1.into(), 2.into()]));
+///
+///     let roundtrip = pythonize(py, &value).unwrap();
+///     assert!(roundtrip.eq(&obj).unwrap());
+/// })
+/// ```
+///
+/// `bytearray` and `memoryview` are read as [`Value::Bytes`], `frozenset` as [`Value::Set`], and
+/// other sequences and mappings as [`Value::List`] and [`Value::Dict`]. With other serializers,
+/// tuples and sets are serialized as sequences and integers outside the range of `i128` and
+/// `u128` as strings.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    None,
+    Bool(bool),
+    Int(Int),
+    Float(f64),
+    Str(String),
+    Bytes(Vec<u8>),
+    List(Vec<Value>),
+    Tuple(Vec<Value>),
+    /// The items of a `set`, in iteration order
+    Set(Vec<Value>),
+    /// The items of a `dict`, in insertion order
+    Dict(Vec<(Value, Value)>),
+}
+
+/// A Python `int` of any size, see [`Value::Int`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Int(IntRepr);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum IntRepr {
+    Small(i128),
+    /// Canonical decimal representation of an integer outside the range of `i128`
+    Big(String),
+}
+
+impl Int {
+    /// The value as an `i64`, if it fits
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_i128().and_then(|i| i.try_into().ok())
+    }
+
+    /// The value as a `u64`, if it fits
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_i128().and_then(|i| i.try_into().ok())
+    }
+
+    /// The value as an `i128`, if it fits
+    pub fn as_i128(&self) -> Option<i128> {
+        match self.0 {
+            IntRepr::Small(i) => Some(i),
+            IntRepr::Big(_) => None,
+        }
+    }
+}
+
+macro_rules! int_from {
+    ($($ty:ty)*) => {
+        $(
+            impl From<$ty> for Int {
+                fn from(i: $ty) -> Self {
+                    Int(IntRepr::Small(i.into()))
+                }
+            }
+
+            impl From<$ty> for Value {
+                fn from(i: $ty) -> Self {
+                    Value::Int(i.into())
+                }
+            }
+        )*
+    };
+}
+
+int_from!(i8 i16 i32 i64 i128 u8 u16 u32 u64);
+
+impl From<u128> for Int {
+    fn from(i: u128) -> Self {
+        match i128::try_from(i) {
+            Ok(i) => Int(IntRepr::Small(i)),
+            Err(_) => Int(IntRepr::Big(i.to_string())),
+        }
+    }
+}
+
+impl Display for Int {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            IntRepr::Small(i) => i.fmt(f),
+            IntRepr::Big(s) => f.write_str(s),
+        }
+    }
+}
+
+impl FromStr for Int {
+    type Err = PythonizeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(i) = s.parse::<i128>() {
+            return Ok(Int(IntRepr::Small(i)));
+        }
+        let (sign, digits) = match s.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", s.strip_prefix('+').unwrap_or(s)),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(PythonizeError::msg(format!("invalid integer `{}`", s)));
+        }
+        let digits = digits.trim_start_matches('0');
+        Ok(Int(IntRepr::Big(format!("{}{}", sign, digits))))
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<Int> for Value {
+    fn from(i: Int) -> Self {
+        Value::Int(i)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(f: f64) -> Self {
+        Value::Float(f)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Str(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Str(s)
+    }
+}
+
+impl Serialize for Int {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match &self.0 {
+            IntRepr::Small(i) => {
+                if let Ok(i) = i64::try_from(*i) {
+                    serializer.serialize_i64(i)
+                } else if let Ok(u) = u64::try_from(*i) {
+                    serializer.serialize_u64(u)
+                } else {
+                    serializer.serialize_i128(*i)
+                }
+            }
+            IntRepr::Big(s) => serializer.serialize_newtype_struct(INT_TOKEN, s),
+        }
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Value::None => serializer.serialize_none(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Int(i) => i.serialize(serializer),
+            Value::Float(f) => serializer.serialize_f64(*f),
+            Value::Str(s) => serializer.serialize_str(s),
+            Value::Bytes(b) => serializer.serialize_bytes(b),
+            Value::List(items) => serializer.collect_seq(items),
+            Value::Tuple(items) => serializer.serialize_newtype_struct(as_::TUPLE_TOKEN, items),
+            Value::Set(items) => serializer.serialize_newtype_struct(as_::SET_TOKEN, items),
+            Value::Dict(entries) => serializer.collect_map(entries.iter().map(|(k, v)| (k, v))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(VALUE_TOKEN, ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any value")
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<Value, E> {
+        Ok(Value::Bool(b))
+    }
+
+    fn visit_i64<E>(self, i: i64) -> Result<Value, E> {
+        Ok(i.into())
+    }
+
+    fn visit_i128<E>(self, i: i128) -> Result<Value, E> {
+        Ok(i.into())
+    }
+
+    fn visit_u64<E>(self, u: u64) -> Result<Value, E> {
+        Ok(u.into())
+    }
+
+    fn visit_u128<E>(self, u: u128) -> Result<Value, E> {
+        Ok(Value::Int(u.into()))
+    }
+
+    fn visit_f64<E>(self, f: f64) -> Result<Value, E> {
+        Ok(Value::Float(f))
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Value, E> {
+        Ok(s.into())
+    }
+
+    fn visit_string<E>(self, s: String) -> Result<Value, E> {
+        Ok(s.into())
+    }
+
+    fn visit_bytes<E>(self, b: &[u8]) -> Result<Value, E> {
+        Ok(Value::Bytes(b.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, b: Vec<u8>) -> Result<Value, E> {
+        Ok(Value::Bytes(b))
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::None)
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::None)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Value::List(items))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut entries = Vec::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(Value::Dict(entries))
+    }
+
+    /// `Depythonizer` tags the values which serde's data model can't tell apart as enum variants
+    fn visit_enum<A>(self, data: A) -> Result<Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        let (tag, variant): (String, _) = data.variant()?;
+        match tag.as_str() {
+            "Tuple" => variant.newtype_variant().map(Value::Tuple),
+            "Set" => variant.newtype_variant().map(Value::Set),
+            "Int" => {
+                let s: String = variant.newtype_variant()?;
+                s.parse().map(Value::Int).map_err(de::Error::custom)
+            }
+            _ => Err(de::Error::unknown_variant(&tag, &["Tuple", "Set", "Int"])),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Int, Value};
+    use crate::{depythonize, pythonize};
+    use pyo3::prelude::*;
+    use pyo3::types::PySet;
+    use serde_json::json;
+
+    const MINUS_2_POW_200: &str = "-1606938044258990275541962092341162602522202993782792835301376";
+
+    #[test]
+    fn test_value_roundtrip() {
+        Python::attach(|py| {
+            let obj = py
+                .eval(
+                    c"{1: (b'x', {1, 2}), 'a': [None, True, 1.5, -2 ** 200], (1, 'b'): frozenset()}",
+                    None,
+                    None,
+                )
+                .unwrap();
+            let value: Value = depythonize(&obj).unwrap();
+            let big: Int = MINUS_2_POW_200.parse().unwrap();
+            assert_eq!(
+                value,
+                Value::Dict(vec![
+                    (
+                        1.into(),
+                        Value::Tuple(vec![
+                            Value::Bytes(b"x".to_vec()),
+                            Value::Set(vec![1.into(), 2.into()]),
+                        ])
+                    ),
+                    (
+                        "a".into(),
+                        Value::List(vec![Value::None, true.into(), 1.5.into(), big.into()])
+                    ),
+                    (Value::Tuple(vec![1.into(), "b".into()]), Value::Set(vec![])),
+                ])
+            );
+
+            let roundtrip = pythonize(py, &value).unwrap();
+            assert!(roundtrip.eq(&obj).unwrap());
+            let set = roundtrip.get_item(1).unwrap().get_item(1).unwrap();
+            assert!(set.is_instance_of::<PySet>());
+            // frozensets are read as sets
+            let set = roundtrip.get_item((1, "b")).unwrap();
+            assert!(set.is_instance_of::<PySet>());
+        });
+    }
+
+    #[test]
+    fn test_value_other_formats() {
+        let value = Value::Dict(vec![(
+            "a".into(),
+            Value::Tuple(vec![
+                Value::Set(vec![1.into()]),
+                MINUS_2_POW_200.parse::<Int>().unwrap().into(),
+            ]),
+        )]);
+        assert_eq!(
+            serde_json::to_value(&value).unwrap(),
+            json!({"a": [[1], MINUS_2_POW_200]})
+        );
+
+        let value: Value = serde_json::from_value(json!({"a": [1, -1, "x", null]})).unwrap();
+        assert_eq!(
+            value,
+            Value::Dict(vec![(
+                "a".into(),
+                Value::List(vec![1.into(), (-1).into(), "x".into(), Value::None])
+            )])
+        );
+    }
+
+    #[test]
+    fn test_int() {
+        let int: Int = "-0042".parse().unwrap();
+        assert_eq!(int.as_i64(), Some(-42));
+        assert_eq!(int.as_u64(), None);
+
+        let int: Int = "+000340282366920938463463374607431768211456"
+            .parse()
+            .unwrap();
+        assert_eq!(int.as_i128(), None);
+        assert_eq!(int.to_string(), "340282366920938463463374607431768211456");
+        assert_eq!(Int::from(u128::MAX).to_string(), u128::MAX.to_string());
+
+        assert!("".parse::<Int>().is_err());
+        assert!("1.5".parse::<Int>().is_err());
+    }
+}
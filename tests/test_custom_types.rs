@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use pyo3::{
     exceptions::{PyIndexError, PyKeyError},
     prelude::*,
-    types::{PyDict, PyMapping, PySequence, PyTuple},
+    types::{PyByteArray, PyBytes, PyDict, PyList, PyMapping, PySequence, PyTuple},
     IntoPyObjectExt,
 };
 use pythonize::{
@@ -283,3 +283,72 @@ fn test_custom_named_dict() {
         assert_eq!(deserialized, json!({ "hello": 1, "world": 2 }));
     })
 }
+
+struct PythonizeByteArray;
+impl PythonizeTypes for PythonizeByteArray {
+    type Map = PyDict;
+    type NamedMap = PythonizeUnnamedMappingAdapter<PyDict>;
+    type List = PyList;
+
+    fn create_bytes<'py>(py: Python<'py>, bytes: &[u8]) -> PyResult<Bound<'py, PyAny>> {
+        Ok(PyByteArray::new(py, bytes).into_any())
+    }
+}
+
+#[test]
+fn test_custom_bytes() {
+    Python::attach(|py| {
+        let data = serde_bytes::Bytes::new(b"abc");
+
+        let serialized = pythonize_custom::<PythonizeByteArray, _>(py, data).unwrap();
+        assert!(serialized.is_instance_of::<PyByteArray>());
+        let deserialized: serde_bytes::ByteBuf = depythonize(&serialized).unwrap();
+        assert_eq!(deserialized, b"abc");
+
+        // Bytes are still `bytes` by default
+        let serialized = pythonize_custom::<PythonizeListTuples, _>(py, data).unwrap();
+        assert!(serialized.is_instance_of::<PyBytes>());
+    })
+}
+
+struct PythonizeListTuples;
+impl PythonizeTypes for PythonizeListTuples {
+    type Map = PyDict;
+    type NamedMap = PythonizeUnnamedMappingAdapter<PyDict>;
+    type List = PyList;
+
+    fn create_tuple<'py, T, U>(
+        py: Python<'py>,
+        elements: impl IntoIterator<Item = T, IntoIter = U>,
+    ) -> PyResult<Bound<'py, PySequence>>
+    where
+        T: IntoPyObject<'py>,
+        U: ExactSizeIterator<Item = T>,
+    {
+        PyList::create_sequence(py, elements)
+    }
+}
+
+#[test]
+fn test_custom_tuple() {
+    #[derive(Serialize)]
+    struct Pair(i32, i32);
+
+    #[derive(Serialize)]
+    enum Shape {
+        Line(Pair, Pair),
+    }
+
+    Python::attach(|py| {
+        let serialized = pythonize_custom::<PythonizeListTuples, _>(py, &(1, "a")).unwrap();
+        assert!(serialized.is_instance_of::<PyList>());
+
+        let serialized =
+            pythonize_custom::<PythonizeListTuples, _>(py, &Shape::Line(Pair(0, 0), Pair(1, 2)))
+                .unwrap();
+        assert_eq!(
+            serialized.repr().unwrap().to_string(),
+            "{'Line': [[0, 0], [1, 2]]}"
+        );
+    })
+}